@@ -1,38 +1,106 @@
+use crate::camera::CameraController;
+use crate::component::Component;
+use crate::debug::DebugOverlay;
+use crate::editor::Editor;
+use crate::graphics::TargetCamera;
+use crate::history::History;
+use crate::hud::Hud;
+use crate::inspector::Inspector;
 use crate::keybinds::*;
-use crate::object::Object;
+use crate::lighting::Lighting;
+use crate::object::{Object, ObjectId};
+use crate::particles::ParticleSystem;
 use crate::physics_world::PhysicsWorld;
+use crate::post_processing::PostProcessing;
+use crate::settings::Settings;
+use crate::starfield::Starfield;
+use crate::trajectory::Trajectory;
+use crate::trigger::TriggerEvent;
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
 
 pub struct App {
     pub paused: bool,
-    pub debug: bool,
+    pub debug: DebugOverlay,
+    pub editor: Editor,
+    pub inspector: Inspector,
+    pub history: History,
     pub fixed_tick_time: f32,
     pub camera: Camera2D,
+    pub camera_controller: CameraController,
     pub material: Material,
+    pub emissive_material: Material,
+    pub lighting: Lighting,
+    pub post_processing: PostProcessing,
+    pub settings: Settings,
+    pub hud: Hud,
+    pub trajectory: Trajectory,
+    pub starfield: Starfield,
     pub keybinds: Keybinds,
     pub physics_world: PhysicsWorld,
     pub objects: Vec<Object>,
+    /// The objects created by `main`, which levels save the state of.
+    /// Levels refer to them by their index in here, which stays the same
+    /// between runs.
+    pub level_objects: Vec<ObjectId>,
+    pub particles: ParticleSystem,
+    /// The object controlled by the keyboard and mouse.
+    pub player: Option<ObjectId>,
+    /// Where the player respawns, set by passing through checkpoints.
+    pub checkpoint: Option<Isometry<Real>>,
+    /// Set by reaching a level exit, which locks the player's controls
+    /// until they respawn.
+    pub level_complete: bool,
+    /// Objects created during the fixed tick, which are added to
+    /// `objects` once it has finished.
+    pub spawned: Vec<Object>,
+    /// Objects to be removed once the fixed tick has finished.
+    pub despawned: Vec<ObjectId>,
+    pub projectile_texture: Texture2D,
 }
 
 impl App {
     pub const FIXED_TICKS_PER_SEC: f32 = 60.0;
     pub const FIXED_DELTA_TIME: f32 = 1.0 / Self::FIXED_TICKS_PER_SEC;
     pub const MAX_TICKS_PER_FRAME: u32 = 5;
+    pub const STARFIELD_SEED: u64 = 404;
 
     pub fn new() -> Self {
         Self {
             paused: false,
-            debug: false,
+            debug: DebugOverlay::new(),
+            editor: Editor::new(),
+            inspector: Inspector::new(),
+            history: History::default(),
             fixed_tick_time: 0.0,
             camera: Camera2D {
                 zoom: Vec2::splat(1.0 / 64.0),
                 ..Default::default()
             },
+            camera_controller: CameraController::new(),
             material: crate::graphics::make_tri_pixel_material(),
+            emissive_material: crate::graphics::make_emissive_material(),
+            lighting: Lighting::new(),
+            post_processing: PostProcessing::new(),
+            settings: Settings::new(),
+            hud: Hud::new(),
+            trajectory: Trajectory::new(),
+            starfield: Starfield::new(Self::STARFIELD_SEED),
             keybinds: Keybinds::default(),
             physics_world: PhysicsWorld::new(),
             objects: Vec::new(),
+            level_objects: Vec::new(),
+            particles: ParticleSystem::default(),
+            player: None,
+            checkpoint: None,
+            level_complete: false,
+            spawned: Vec::new(),
+            despawned: Vec::new(),
+            projectile_texture: Texture2D::from_image(&Image::gen_image_color(
+                2,
+                2,
+                Color::from_hex(0xffe080),
+            )),
         }
     }
 
@@ -44,7 +112,106 @@ impl App {
         }
 
         if self.keybinds.get(KeyAction::Debug).is_just_pressed() {
-            self.debug ^= true;
+            self.debug.enabled ^= true;
+        }
+
+        let mut zoom = mouse_wheel().1.signum();
+
+        if self.keybinds.get(KeyAction::ZoomIn).is_just_pressed() {
+            zoom += 1.0;
+        }
+
+        if self.keybinds.get(KeyAction::ZoomOut).is_just_pressed() {
+            zoom -= 1.0;
+        }
+
+        self.camera_controller.zoom_by(zoom);
+
+        if self.keybinds.get(KeyAction::TacticalView).is_just_pressed() {
+            self.camera_controller.tactical ^= true;
+        }
+
+        if self.keybinds.get(KeyAction::PixelPerfect).is_just_pressed() {
+            self.camera_controller.pixel_perfect ^= true;
+        }
+
+        if self.keybinds.get(KeyAction::Hud).is_just_pressed() {
+            self.hud.enabled ^= true;
+        }
+
+        if self.keybinds.get(KeyAction::Trajectory).is_just_pressed() {
+            self.trajectory.enabled ^= true;
+        }
+
+        if self
+            .keybinds
+            .get(KeyAction::PostProcessing)
+            .is_just_pressed()
+        {
+            self.settings.post_processing ^= true;
+        }
+
+        if self.keybinds.get(KeyAction::Bloom).is_just_pressed() {
+            self.settings.bloom ^= true;
+        }
+
+        if self.keybinds.get(KeyAction::Crt).is_just_pressed() {
+            self.settings.crt ^= true;
+        }
+
+        if self
+            .keybinds
+            .get(KeyAction::ChromaticAberration)
+            .is_just_pressed()
+        {
+            self.settings.chromatic_aberration ^= true;
+        }
+
+        if self
+            .keybinds
+            .get(KeyAction::DamageVignette)
+            .is_just_pressed()
+        {
+            self.settings.damage_vignette ^= true;
+        }
+
+        if self.keybinds.get(KeyAction::SwitchShip).is_just_pressed() {
+            self.switch_ship();
+        }
+
+        if self.keybinds.get(KeyAction::Respawn).is_just_pressed() {
+            self.respawn();
+        }
+
+        if self.keybinds.get(KeyAction::Editor).is_just_pressed() {
+            self.editor.enabled ^= true;
+        }
+
+        if self.editor.enabled {
+            self.editor.update(
+                &self.keybinds,
+                &self.camera,
+                &mut self.history,
+                &mut self.physics_world,
+                &mut self.objects,
+                &self.level_objects,
+            );
+        }
+
+        if self.keybinds.get(KeyAction::Inspector).is_just_pressed() {
+            self.inspector.enabled ^= true;
+        }
+
+        if self.inspector.enabled {
+            // Clicking while editing paints tiles instead
+            self.inspector.update(
+                &self.camera,
+                !self.editor.enabled,
+                &mut self.history,
+                &mut self.physics_world,
+                &mut self.objects,
+                &self.level_objects,
+            );
         }
 
         unsafe {
@@ -59,13 +226,39 @@ impl App {
 
         self.update_camera();
 
+        self.lighting.render(
+            &self.camera,
+            &self.physics_world,
+            &self.objects,
+            self.interpolation(),
+            get_frame_time(),
+        );
+
+        let light_map = self.lighting.light_map().clone();
+        self.material.set_texture("LightMap", light_map.clone());
+        self.material.set_uniform("Lit", 1.0f32);
+        self.emissive_material.set_texture("LightMap", light_map);
+        self.emissive_material.set_uniform("Lit", 0.0f32);
+
+        let scene_target = self.post_processing.scene_target(&self.settings);
+        set_camera(&TargetCamera::new(&self.camera, scene_target.clone()));
+
         clear_background(BLACK);
 
-        crate::graphics::draw_stars_around(self.camera.target);
+        self.starfield.draw(&self.camera, scene_target, get_time());
+
+        if !self.paused {
+            self.particles.update(get_frame_time());
+        }
+
+        self.particles.draw();
 
         for object in &self.objects {
             gl_use_material(&self.material);
-            object.draw(&mut self.physics_world);
+            object.draw(&self.physics_world, self.interpolation());
+
+            gl_use_material(&self.emissive_material);
+            object.draw_emissive(&self.physics_world, self.interpolation());
 
             for component in &object.components {
                 component.draw(object, self);
@@ -74,24 +267,65 @@ impl App {
 
         gl_use_default_material();
 
-        if self.debug {
-            for object in &self.objects {
-                object.draw_debug(&mut self.physics_world);
-            }
+        self.post_processing
+            .apply(&self.settings, get_time() as f32, get_frame_time());
+        set_camera(&self.camera);
+
+        if self.debug.enabled {
+            self.debug
+                .draw(&self.camera, &self.physics_world, &self.objects);
+        }
+
+        if self.trajectory.enabled {
+            self.trajectory.draw(
+                &self.camera,
+                &self.physics_world,
+                &self.objects,
+                self.player,
+            );
+        }
+
+        if self.hud.enabled {
+            self.hud.draw(
+                &self.camera,
+                &self.physics_world,
+                &self.objects,
+                self.player,
+                self.level_complete,
+            );
+        }
+
+        if self.inspector.enabled {
+            self.inspector
+                .draw(&self.camera, &self.physics_world, &self.objects);
+        }
+
+        if self.editor.enabled {
+            self.editor
+                .draw(&self.camera, &self.physics_world, &self.objects);
         }
     }
 
     pub fn check_fixed_tick(&mut self) {
         self.fixed_tick_time += get_frame_time() * Self::FIXED_TICKS_PER_SEC;
 
-        for _ in 0..(self.fixed_tick_time as u32).min(Self::MAX_TICKS_PER_FRAME) {
+        let ticks = (self.fixed_tick_time as u32).min(Self::MAX_TICKS_PER_FRAME);
+
+        for _ in 0..ticks {
             self.fixed_tick();
         }
 
+        self.debug.ticks_per_frame = ticks;
+
         self.fixed_tick_time %= 1.0;
     }
 
     fn fixed_tick(&mut self) {
+        for object in &mut self.objects {
+            object.previous_position =
+                *self.physics_world.rigid_body_set[object.rigid_body].position();
+        }
+
         unsafe {
             let app_ptr = self as *mut App;
             for object in &mut self.objects {
@@ -111,14 +345,139 @@ impl App {
 
         if !self.paused {
             self.physics_world.step();
+            self.send_trigger_events();
+        }
+
+        self.objects.append(&mut self.spawned);
+
+        for id in std::mem::take(&mut self.despawned) {
+            crate::history::remove_object(&mut self.physics_world, &mut self.objects, id);
         }
     }
 
+    /// Tells the components of the objects involved in each sensor event
+    /// from the last physics step. Objects which have been removed are
+    /// left out.
+    fn send_trigger_events(&mut self) {
+        for event in std::mem::take(&mut self.physics_world.sensor_events) {
+            let find = |collider| {
+                self.objects
+                    .iter()
+                    .position(|object| object.collider == collider)
+            };
+
+            let (Some(zone), Some(visitor)) = (find(event.sensor), find(event.other)) else {
+                continue;
+            };
+
+            let trigger = TriggerEvent {
+                zone: self.objects[zone].id,
+                visitor: self.objects[visitor].id,
+                kind: event.kind,
+            };
+
+            unsafe {
+                let app_ptr = self as *mut App;
+                for index in [zone, visitor] {
+                    let object_ptr = &mut self.objects[index] as *mut Object;
+                    for component in &mut (*object_ptr).components {
+                        component.on_trigger(
+                            object_ptr.as_mut().unwrap(),
+                            app_ptr.as_mut().unwrap(),
+                            &trigger,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the player back to the last checkpoint they passed, at rest,
+    /// and lets them carry on after finishing the level.
+    fn respawn(&mut self) {
+        self.level_complete = false;
+
+        let Some(checkpoint) = self.checkpoint else {
+            return;
+        };
+
+        let Some(player) = self
+            .objects
+            .iter_mut()
+            .find(|object| Some(object.id) == self.player)
+        else {
+            return;
+        };
+
+        let rigid_body = &mut self.physics_world.rigid_body_set[player.rigid_body];
+        rigid_body.set_position(checkpoint, true);
+        rigid_body.set_linvel(Vector::zeros(), true);
+        rigid_body.set_angvel(0.0, true);
+
+        player.previous_position = checkpoint;
+        self.camera_controller.snap();
+    }
+
     fn update_camera(&mut self) {
-        self.camera.zoom.x = self.camera.zoom.y / screen_width() * screen_height();
+        self.camera_controller
+            .update(&mut self.camera, get_frame_time());
         set_camera(&self.camera);
     }
 
+    /// How far between the last two fixed ticks to draw objects, from 0
+    /// to 1.
+    pub fn interpolation(&self) -> f32 {
+        self.fixed_tick_time
+    }
+
+    /// The position of an object's rigid body as it should be drawn this
+    /// frame.
+    pub fn interpolated_position(&self, object: &Object) -> Isometry<Real> {
+        object.interpolated_position(&self.physics_world, self.interpolation())
+    }
+
+    pub fn is_player(&self, object: &Object) -> bool {
+        self.player == Some(object.id)
+    }
+
+    /// Gives control to the next object after the player which has a
+    /// `Motion` component.
+    fn switch_ship(&mut self) {
+        let current = self
+            .objects
+            .iter()
+            .position(|object| self.is_player(object))
+            .unwrap_or(self.objects.len());
+
+        let controllable = |object: &&Object| {
+            object
+                .components
+                .iter()
+                .any(|component| matches!(component, Component::Motion { .. }))
+        };
+
+        let next = self.objects[current..]
+            .iter()
+            .skip(1)
+            .chain(&self.objects[..current])
+            .find(controllable);
+
+        if let Some(next) = next {
+            self.player = Some(next.id);
+            self.camera_controller.snap();
+        }
+    }
+
+    pub fn spawn(&mut self, object: Object) {
+        self.spawned.push(object);
+    }
+
+    pub fn despawn(&mut self, object: &Object) {
+        if !self.despawned.contains(&object.id) {
+            self.despawned.push(object.id);
+        }
+    }
+
     pub fn get_rigid_body(&self, object: &Object) -> &RigidBody {
         &self.physics_world.rigid_body_set[object.rigid_body]
     }
@@ -135,3 +494,9 @@ impl App {
         &mut self.physics_world.collider_set[object.collider]
     }
 }
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,208 @@
+use macroquad::prelude::*;
+
+/// Moves the camera smoothly towards a target, leading it by the
+/// target's velocity and by where the mouse is aiming. Also controls
+/// the zoom, which can be snapped so that each texel covers a whole
+/// number of screen pixels.
+#[derive(Clone, Debug)]
+pub struct CameraController {
+    /// The point being followed, usually set by `Component::CameraFollow`.
+    pub target: Vec2,
+    pub target_velocity: Vec2,
+    /// Roughly the time in seconds the camera takes to catch up to the
+    /// target. The camera is critically damped, so it never overshoots.
+    pub smooth_time: f32,
+    /// Seconds of the target's velocity to look ahead by.
+    pub look_ahead: f32,
+    pub max_look_ahead: f32,
+    /// The fraction of the distance from the center of the screen to
+    /// the mouse to move the view by.
+    pub mouse_bias: f32,
+    pub max_mouse_bias: f32,
+    /// How far the target can move from the center of the view before
+    /// the camera starts to follow.
+    pub dead_zone: f32,
+    /// From 0 to 1. The strength of the screen shake is the square of
+    /// the trauma, so small hits barely shake the screen.
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub trauma_decay: f32,
+    pub max_shake_offset: f32,
+    /// In degrees.
+    pub max_shake_rotation: f32,
+    /// World units visible from the top to the bottom of the screen.
+    pub view_height: f32,
+    pub min_view_height: f32,
+    pub max_view_height: f32,
+    /// Rounds the zoom so that tiles are a whole number of
+    /// `Tile::SIZE_TEXELS` pixels across, keeping tri-pixels crisp.
+    pub pixel_perfect: bool,
+    /// Zooms far out to show the surroundings.
+    pub tactical: bool,
+    pub tactical_view_height: f32,
+    /// Roughly the time in seconds to reach a new zoom level.
+    pub zoom_smooth_time: f32,
+    current_view_height: f32,
+    position: Vec2,
+    velocity: Vec2,
+    anchor: Vec2,
+    snap: bool,
+    time: f32,
+}
+
+impl CameraController {
+    pub const ZOOM_STEP: f32 = 1.1;
+
+    pub fn new() -> Self {
+        Self {
+            target: Vec2::ZERO,
+            target_velocity: Vec2::ZERO,
+            smooth_time: 0.15,
+            look_ahead: 0.4,
+            max_look_ahead: 40.0,
+            mouse_bias: 0.2,
+            max_mouse_bias: 24.0,
+            dead_zone: 2.0,
+            trauma: 0.0,
+            trauma_decay: 1.5,
+            max_shake_offset: 3.0,
+            max_shake_rotation: 2.0,
+            view_height: 128.0,
+            min_view_height: 32.0,
+            max_view_height: 512.0,
+            pixel_perfect: true,
+            tactical: false,
+            tactical_view_height: 2048.0,
+            zoom_smooth_time: 0.1,
+            current_view_height: 128.0,
+            position: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            anchor: Vec2::ZERO,
+            snap: true,
+            time: 0.0,
+        }
+    }
+
+    /// Each step zooms in by `ZOOM_STEP`, or out for negative steps.
+    pub fn zoom_by(&mut self, steps: f32) {
+        self.view_height = (self.view_height * Self::ZOOM_STEP.powf(-steps))
+            .clamp(self.min_view_height, self.max_view_height);
+    }
+
+    /// The number of screen pixels per world unit.
+    pub fn pixels_per_unit(&self) -> f32 {
+        let pixels_per_unit = screen_height() / self.current_view_height;
+
+        if self.pixel_perfect && !self.tactical {
+            // A texel is one world unit, so whole pixels per unit makes
+            // each tile a whole multiple of `Tile::SIZE_TEXELS` pixels
+            pixels_per_unit.round().max(1.0)
+        } else {
+            pixels_per_unit
+        }
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Makes the camera jump straight to its target on the next update,
+    /// such as after switching to another ship.
+    pub fn snap(&mut self) {
+        self.snap = true;
+    }
+
+    pub fn update(&mut self, camera: &mut Camera2D, delta_time: f32) {
+        self.time += delta_time;
+
+        let view_height = if self.tactical {
+            self.tactical_view_height
+        } else {
+            self.view_height
+        };
+
+        let progress = 1.0 - (-delta_time / self.zoom_smooth_time.max(0.0001)).exp();
+        self.current_view_height *= (view_height / self.current_view_height).powf(progress);
+
+        let pixels_per_unit = self.pixels_per_unit();
+        camera.zoom = vec2(
+            2.0 * pixels_per_unit / screen_width(),
+            2.0 * pixels_per_unit / screen_height(),
+        );
+
+        let aim = camera.screen_to_world(mouse_position().into()) - camera.target;
+
+        let look_ahead =
+            (self.target_velocity * self.look_ahead).clamp_length_max(self.max_look_ahead);
+        let bias = (aim * self.mouse_bias).clamp_length_max(self.max_mouse_bias);
+        let goal = self.target + look_ahead + bias;
+
+        if self.snap {
+            self.snap = false;
+            self.anchor = goal;
+            self.position = goal;
+            self.velocity = Vec2::ZERO;
+        }
+
+        let from_anchor = goal - self.anchor;
+        if from_anchor.length() > self.dead_zone {
+            self.anchor = goal - from_anchor.normalize() * self.dead_zone;
+        }
+
+        (self.position, self.velocity) = smooth_damp(
+            self.position,
+            self.anchor,
+            self.velocity,
+            self.smooth_time,
+            delta_time,
+        );
+
+        let shake = self.trauma * self.trauma;
+        let offset = vec2(noise(self.time, 0.0), noise(self.time, 10.0));
+
+        camera.target = self.position + offset * self.max_shake_offset * shake;
+
+        if self.pixel_perfect {
+            camera.target = (camera.target * pixels_per_unit).round() / pixels_per_unit;
+        }
+
+        camera.rotation = noise(self.time, 20.0) * self.max_shake_rotation * shake;
+
+        self.trauma = (self.trauma - self.trauma_decay * delta_time).max(0.0);
+    }
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves `current` towards `target` as a critically damped spring,
+/// returning the new position and velocity. Stable for any time step.
+fn smooth_damp(
+    current: Vec2,
+    target: Vec2,
+    velocity: Vec2,
+    smooth_time: f32,
+    delta_time: f32,
+) -> (Vec2, Vec2) {
+    let omega = 2.0 / smooth_time.max(0.0001);
+    let x = omega * delta_time;
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+
+    let change = current - target;
+    let temp = (velocity + omega * change) * delta_time;
+
+    let velocity = (velocity - omega * temp) * decay;
+    let position = target + (change + temp) * decay;
+
+    (position, velocity)
+}
+
+/// Smooth noise from -1 to 1, which differs for each `seed`.
+fn noise(time: f32, seed: f32) -> f32 {
+    let time = time * 25.0 + seed;
+
+    (time.sin() * 0.5 + (time * 2.3).sin() * 0.3 + (time * 4.1).sin() * 0.2).clamp(-1.0, 1.0)
+}
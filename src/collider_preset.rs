@@ -0,0 +1,49 @@
+use rapier2d::prelude::*;
+use std::fmt::Debug;
+
+/// One of a set of named settings for colliders, like collision layers
+/// and physics materials, which is read back from the collider's own
+/// fields rather than stored alongside it.
+pub trait ColliderPreset: Copy + PartialEq + Debug + 'static {
+    const PRESETS: &'static [Self];
+
+    fn name(self) -> &'static str;
+
+    /// Gives an existing collider this preset.
+    fn set(self, collider: &mut Collider);
+
+    /// Whether `collider` has this preset.
+    fn matches(self, collider: &Collider) -> bool;
+
+    /// The preset of a collider, or `None` if it doesn't match any.
+    fn of(collider: &Collider) -> Option<Self> {
+        Self::PRESETS
+            .iter()
+            .copied()
+            .find(|preset| preset.matches(collider))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision_layer::CollisionLayer;
+    use crate::material::PhysicsMaterial;
+
+    fn assert_read_back<T: ColliderPreset>() {
+        for &preset in T::PRESETS {
+            let mut collider = ColliderBuilder::ball(1.0).build();
+            preset.set(&mut collider);
+
+            assert_eq!(T::of(&collider), Some(preset));
+        }
+
+        assert_eq!(T::of(&ColliderBuilder::ball(1.0).build()), None);
+    }
+
+    #[test]
+    fn presets_are_read_back_from_colliders() {
+        assert_read_back::<CollisionLayer>();
+        assert_read_back::<PhysicsMaterial>();
+    }
+}
@@ -0,0 +1,204 @@
+use crate::collider_preset::ColliderPreset;
+use rapier2d::prelude::*;
+
+/// What kind of thing a collider is, which decides what it collides with.
+/// A collider's layer is kept in its collision groups.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollisionLayer {
+    Ship,
+    Projectile,
+    Station,
+    /// Pieces broken off of tile maps, which don't collide with each
+    /// other.
+    Debris,
+    /// Areas like force fields, which only detect what passes through.
+    Sensor,
+}
+
+impl CollisionLayer {
+    pub const LAYERS: [Self; 5] = [
+        Self::Ship,
+        Self::Projectile,
+        Self::Station,
+        Self::Debris,
+        Self::Sensor,
+    ];
+
+    fn group(self) -> Group {
+        match self {
+            Self::Ship => Group::GROUP_1,
+            Self::Projectile => Group::GROUP_2,
+            Self::Station => Group::GROUP_3,
+            Self::Debris => Group::GROUP_4,
+            Self::Sensor => Group::GROUP_5,
+        }
+    }
+
+    /// The layers this layer collides with. Two colliders only collide if
+    /// both of their layers collide with the other, so this is kept
+    /// symmetric.
+    pub fn collides_with(self) -> &'static [Self] {
+        match self {
+            Self::Ship => &[
+                Self::Ship,
+                Self::Projectile,
+                Self::Station,
+                Self::Debris,
+                Self::Sensor,
+            ],
+            Self::Projectile => &[Self::Ship, Self::Station, Self::Debris, Self::Sensor],
+            Self::Station => &[Self::Ship, Self::Projectile, Self::Station, Self::Debris],
+            Self::Debris => &[Self::Ship, Self::Projectile, Self::Station, Self::Sensor],
+            Self::Sensor => &[Self::Ship, Self::Projectile, Self::Debris],
+        }
+    }
+
+    /// The collision groups of a collider on this layer.
+    pub fn groups(self) -> InteractionGroups {
+        let filter = self
+            .collides_with()
+            .iter()
+            .fold(Group::NONE, |filter, layer| filter | layer.group());
+
+        InteractionGroups::new(self.group(), filter)
+    }
+}
+
+/// Colliders without a layer collide with everything.
+impl ColliderPreset for CollisionLayer {
+    const PRESETS: &'static [Self] = &Self::LAYERS;
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ship => "ship",
+            Self::Projectile => "projectile",
+            Self::Station => "station",
+            Self::Debris => "debris",
+            Self::Sensor => "sensor",
+        }
+    }
+
+    fn set(self, collider: &mut Collider) {
+        collider.set_collision_groups(self.groups());
+    }
+
+    fn matches(self, collider: &Collider) -> bool {
+        collider.collision_groups().memberships == self.group()
+    }
+}
+
+/// Stops `collider` from colliding with the rigid body `owner`, like a
+/// projectile with the ship which fired it.
+pub fn ignore_owner(collider: ColliderBuilder, owner: RigidBodyHandle) -> ColliderBuilder {
+    let (index, generation) = owner.into_raw_parts();
+
+    // The top bit marks that there is an owner, as user data is zero by
+    // default
+    collider
+        .user_data(1 << 127 | (generation as u128) << 32 | index as u128)
+        .active_hooks(ActiveHooks::FILTER_CONTACT_PAIRS)
+}
+
+fn owner(collider: &Collider) -> Option<RigidBodyHandle> {
+    let user_data = collider.user_data;
+    if user_data >> 127 == 0 {
+        return None;
+    }
+
+    Some(RigidBodyHandle::from_raw_parts(
+        user_data as u32,
+        (user_data >> 32) as u32,
+    ))
+}
+
+/// Physics hooks which filter out contacts between colliders and their
+/// owners, as set by `ignore_owner`.
+pub struct OwnerFilter;
+
+impl PhysicsHooks for OwnerFilter {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        let collider1 = &context.colliders[context.collider1];
+        let collider2 = &context.colliders[context.collider2];
+
+        let owned = |collider: &Collider, other: Option<RigidBodyHandle>| {
+            owner(collider).is_some_and(|owner| Some(owner) == other)
+        };
+
+        if owned(collider1, context.rigid_body2) || owned(collider2, context.rigid_body1) {
+            return None;
+        }
+
+        Some(SolverFlags::COMPUTE_IMPULSES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_world::PhysicsWorld;
+
+    #[test]
+    fn layers_collide_both_ways() {
+        for layer in CollisionLayer::LAYERS {
+            for other in CollisionLayer::LAYERS {
+                assert_eq!(
+                    layer.groups().test(other.groups()),
+                    other.groups().test(layer.groups()),
+                    "{:?} and {:?}",
+                    layer,
+                    other,
+                );
+            }
+        }
+
+        assert!(!CollisionLayer::Debris
+            .groups()
+            .test(CollisionLayer::Debris.groups()));
+        assert!(!CollisionLayer::Projectile
+            .groups()
+            .test(CollisionLayer::Projectile.groups()));
+    }
+
+    #[test]
+    fn projectile_ignores_owner() {
+        let mut physics_world = PhysicsWorld::new();
+
+        let ship_collider = ColliderBuilder::ball(2.0)
+            .collision_groups(CollisionLayer::Ship.groups())
+            .build();
+        let (ship, ship_collider) =
+            physics_world.add_rigidbody(RigidBodyBuilder::dynamic().build(), ship_collider);
+        let (_, other_collider) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![10.0, 0.0])
+                .build(),
+            ColliderBuilder::ball(2.0)
+                .collision_groups(CollisionLayer::Ship.groups())
+                .build(),
+        );
+
+        // Both fired by the first ship, overlapping each ship
+        for position in [0.0, 10.0] {
+            physics_world.add_rigidbody(
+                RigidBodyBuilder::dynamic()
+                    .translation(vector![position, 1.0])
+                    .build(),
+                ignore_owner(ColliderBuilder::ball(0.5), ship)
+                    .collision_groups(CollisionLayer::Projectile.groups())
+                    .build(),
+            );
+        }
+
+        physics_world.step();
+
+        let touching = |collider| {
+            physics_world
+                .narrow_phase
+                .contact_pairs_with(collider)
+                .any(|pair| pair.has_any_active_contact)
+        };
+
+        assert!(!touching(ship_collider));
+        assert!(touching(other_collider));
+    }
+}
@@ -1,36 +1,212 @@
 use crate::app::App;
+use crate::collision_layer::{ignore_owner, CollisionLayer};
+use crate::flight_model::{FlightInput, FlightModel};
+use crate::force_field::{FieldKind, ForceField};
+use crate::inspector::Inspect;
 use crate::keybinds::KeyAction;
-use crate::object::Object;
-use crate::tilemap::TileMap;
-use macroquad::{miniquad::window::screen_size, prelude::*};
-use nalgebra::{Complex, Unit};
+use crate::lighting::Light;
+use crate::object::{Object, ObjectId};
+use crate::particles::ParticleStyle;
+use crate::resources::Resources;
+use crate::tilemap::{TileMap, TileType};
+use crate::trigger::{TriggerAction, TriggerEvent, TriggerKind};
+use macroquad::prelude::*;
 use rapier2d::prelude::*;
+use std::f32::consts::PI;
 
 #[derive(Clone, Debug)]
 pub enum Component {
     CameraFollow,
+    /// For objects with a tile map with thrusters, the main engine's
+    /// thrust is applied by each thruster at its position rather than at
+    /// the center of mass.
+    ///
+    /// Burns fuel and builds up heat from the object's `Resources`, if
+    /// it has them.
     Motion {
-        power: f32,
-        brake: f32,
+        flight_model: FlightModel,
         emitter: Vec2,
     },
+    /// Turns the object to face the mouse, as quickly as the flight model
+    /// of its `Motion` allows.
     FaceMouse,
     TileMap(TileMap),
+    /// Uses energy and builds up heat from the object's `Resources` with
+    /// each shot, if it has them.
+    Gun {
+        /// Seconds between each shot.
+        cooldown: f32,
+        timer: f32,
+        speed: f32,
+        damage: u32,
+        muzzle: Vec2,
+        energy_cost: f32,
+        heat_cost: f32,
+    },
+    Projectile {
+        damage: u32,
+        /// Seconds until the projectile is despawned.
+        lifetime: f32,
+    },
+    /// A point light at `offset` in the object's local space.
+    Light {
+        offset: Vec2,
+        color: Color,
+        radius: f32,
+    },
+    Resources(Resources),
+    /// Docks with a fixed joint to a docking port on another object once
+    /// the two are close together and facing each other. The player
+    /// undocks on command.
+    DockingPort {
+        offset: Vec2,
+        /// The direction the port faces in the object's local space, in
+        /// radians.
+        angle: f32,
+        joint: Option<ImpulseJointHandle>,
+        /// Whether the port can dock. Cleared on undocking until the port
+        /// has moved away, so it doesn't dock again straight away.
+        armed: bool,
+    },
+    /// A cable from `anchor` which the player can attatch to the nearest
+    /// object within `length`, which is then hauled along by a rope
+    /// joint.
+    TowCable {
+        anchor: Vec2,
+        length: f32,
+        joint: Option<ImpulseJointHandle>,
+    },
+    /// Accelerates every other dynamic body inside of the field, centered
+    /// on the object's position.
+    ForceField(ForceField),
+    /// Reacts to objects entering the object's sensor collider, which
+    /// should be made with `trigger_collider`.
+    TriggerZone {
+        action: TriggerAction,
+        /// Only reacts to the player.
+        player_only: bool,
+        /// The objects inside of the zone.
+        occupants: Vec<ObjectId>,
+    },
 }
 
 impl Component {
+    /// Exhaust particles per second from each thruster.
+    pub const EXHAUST_RATE: f32 = 120.0;
+    pub const SPARKS_PER_DAMAGE: u32 = 2;
+    pub const MAX_SPARKS: u32 = 12;
+    pub const MUZZLE_SPARKS: u32 = 4;
+    /// Particles spawned when a tile is destroyed.
+    pub const EXPLOSION_PARTICLES: u32 = 24;
+    pub const EXHAUST_COLOR: Color = Color::new(1.0, 0.55, 0.2, 0.8);
+    pub const MUZZLE_FLASH_COLOR: Color = Color::new(1.0, 0.9, 0.6, 1.0);
+    pub const EXPLOSION_COLOR: Color = Color::new(1.0, 0.6, 0.3, 1.0);
+    pub const CABLE_COLOR: Color = Color::new(0.7, 0.7, 0.75, 1.0);
+    /// How close two docking ports need to be to dock.
+    pub const DOCKING_DISTANCE: f32 = 2.0;
+    /// How far in radians two docking ports can be from facing each
+    /// other and still dock.
+    pub const DOCKING_ANGLE: f32 = 0.3;
+
     /// Occurs during the fixed timestep, just before the `physics_update`
     /// is called for a given component.
-    pub fn fixed_update(&mut self, _object: &mut Object, _app: &mut App) {
+    pub fn fixed_update(&mut self, object: &mut Object, app: &mut App) {
         match self {
             Self::CameraFollow => {}
             Self::Motion {
-                power: _,
-                brake: _,
+                flight_model: _,
                 emitter: _,
             } => {}
             Self::FaceMouse => {}
-            Self::TileMap(_) => {}
+            Self::TileMap(tile_map) => {
+                // Splitting while editing would throw off parts of a tile
+                // map that is still being drawn
+                if tile_map.check_connectivity && !app.editor.enabled {
+                    for island in tile_map.islands().into_iter().skip(1) {
+                        let fragment = tile_map.split_off(&island);
+                        let fragment = object.make_fragment(&mut app.physics_world, fragment);
+
+                        // Undoing earlier edits to these tiles should change
+                        // the fragment, now that they're part of it
+                        app.history.move_tiles(object.id, fragment.id, &island);
+
+                        app.spawn(fragment);
+                    }
+
+                    tile_map.check_connectivity = false;
+                }
+
+                // Colliders restored from the history don't have their
+                // part materials yet
+                let has_materials = app
+                    .physics_world
+                    .part_materials
+                    .contains_key(&object.collider);
+
+                if tile_map.shape_changed || !has_materials {
+                    tile_map.shape_changed = false;
+
+                    let tile_size = object.size / tile_map.size().as_vec2();
+                    let shape = tile_map.make_shape(tile_size, -object.size * object.offset);
+
+                    let mut mass_properties =
+                        tile_map.mass_properties(tile_size, -object.size * object.offset);
+                    if let Some(mass) = tile_map.tuned_mass {
+                        mass_properties.set_mass(mass, true);
+                    }
+
+                    match shape {
+                        Some(shape) => {
+                            let collider = app.get_collider_mut(object);
+                            collider.set_shape(shape);
+                            collider.set_enabled(true);
+
+                            app.physics_world
+                                .set_mass_properties(object.collider, mass_properties);
+                        }
+                        None => app.get_collider_mut(object).set_enabled(false),
+                    }
+
+                    app.physics_world
+                        .set_part_materials(object.collider, tile_map.materials());
+                }
+            }
+            Self::Gun {
+                cooldown: _,
+                timer: _,
+                speed: _,
+                damage: _,
+                muzzle: _,
+                energy_cost: _,
+                heat_cost: _,
+            } => {}
+            Self::Projectile {
+                damage: _,
+                lifetime: _,
+            } => {}
+            Self::Light {
+                offset: _,
+                color: _,
+                radius: _,
+            } => {}
+            Self::Resources(_) => {}
+            Self::DockingPort {
+                offset: _,
+                angle: _,
+                joint: _,
+                armed: _,
+            } => {}
+            Self::TowCable {
+                anchor: _,
+                length: _,
+                joint: _,
+            } => {}
+            Self::ForceField(_) => {}
+            Self::TriggerZone {
+                action: _,
+                player_only: _,
+                occupants: _,
+            } => {}
         }
     }
 
@@ -41,39 +217,396 @@ impl Component {
         match self {
             Self::CameraFollow => {}
             Self::Motion {
-                power,
-                brake,
-                emitter: _,
+                flight_model,
+                emitter,
             } => {
-                if app.keybinds.get(KeyAction::Boost).is_pressed() {
-                    let rigid_body = app.get_rigid_body_mut(object);
-                    let rotation = rigid_body.rotation();
-                    let rotation = vector![rotation.re, rotation.im];
-                    rigid_body.apply_impulse(rotation * *power, true);
+                if !app.is_player(object) {
+                    return;
+                }
+
+                let pressed = |action| app.keybinds.get(action).is_pressed() as u8 as f32;
+                let input = if app.level_complete {
+                    FlightInput::default()
+                } else {
+                    FlightInput {
+                        thrust: app.keybinds.get(KeyAction::Boost).is_pressed(),
+                        retro: app.keybinds.get(KeyAction::Slow).is_pressed(),
+                        strafe: pressed(KeyAction::StrafeRight) - pressed(KeyAction::StrafeLeft),
+                    }
+                };
+
+                let thrusters = object.thruster_positions();
+                // Objects without thruster tiles have a single main engine
+                let engines = thrusters.len().max(1);
+                let exhausts = object.exhaust_positions(*emitter);
+
+                let can_thrust = object.resources().is_none_or(Resources::can_thrust);
+
+                let rigid_body = app.get_rigid_body_mut(object);
+                let output =
+                    flight_model.update(rigid_body, input, can_thrust, App::FIXED_DELTA_TIME);
+
+                if thrusters.is_empty() {
+                    rigid_body.apply_impulse(output.main, true);
                 }
-                if app.keybinds.get(KeyAction::Slow).is_pressed() {
-                    let rigid_body = app.get_rigid_body_mut(object);
-                    rigid_body.set_linvel(rigid_body.linvel() * *brake, true);
+
+                for thruster in thrusters {
+                    let point = rigid_body.position().transform_point(&thruster.into());
+                    rigid_body.apply_impulse_at_point(output.main, point, true);
+                }
+
+                rigid_body.apply_impulse(output.rcs, true);
+
+                if let Some(resources) = object.resources_mut() {
+                    let burn = output.burn(engines);
+                    resources.burn_fuel(burn * flight_model.fuel_use * App::FIXED_DELTA_TIME);
+                    resources.add_heat(burn * resources.thrust_heat * App::FIXED_DELTA_TIME);
+                }
+
+                if output.main == Vector::zeros() {
+                    return;
+                }
+
+                let rigid_body = &app.physics_world.rigid_body_set[object.rigid_body];
+
+                for exhaust in exhausts {
+                    app.particles.emit_from(
+                        &ParticleStyle::EXHAUST,
+                        rigid_body,
+                        exhaust,
+                        -Vec2::X,
+                        Self::EXHAUST_RATE,
+                        App::FIXED_DELTA_TIME,
+                    );
                 }
             }
             Self::FaceMouse => {
+                if !app.is_player(object) {
+                    return;
+                }
+
+                let flight_model = object.flight_model().cloned().unwrap_or_default();
+                let mouse = app.camera.screen_to_world(mouse_position().into());
+
                 let rigid_body = app.get_rigid_body_mut(object);
-                let mouse = Vec2::from(mouse_position_local()) * Vec2::from(screen_size());
-                let target = Complex::<f32>::new(mouse.x, mouse.y);
-                let target = Unit::<Complex<f32>>::new_normalize(target);
-                let angle = rigid_body.rotation().angle_to(&target) * std::f32::consts::PI;
-                if angle.is_finite() {
-                    let inertia = rigid_body.mass_properties().effective_angular_inertia();
+                let target = mouse - Vec2::from(*rigid_body.translation());
+                let impulse = flight_model.turn_impulse(rigid_body, target, App::FIXED_DELTA_TIME);
+
+                rigid_body.apply_torque_impulse(impulse, true);
+            }
+            Self::TileMap(tile_map) => {
+                let tile_size = object.size / tile_map.size().as_vec2();
+                let origin = -object.size * object.offset;
+
+                let damaged = collision_damage(
+                    &app.physics_world.narrow_phase,
+                    object.collider,
+                    tile_size,
+                    origin,
+                );
+
+                for (index, damage, point, normal) in damaged {
+                    let damage = object
+                        .resources_mut()
+                        .map_or(damage, |resources| resources.shield(damage));
+                    if damage == 0 {
+                        continue;
+                    }
 
-                    let velocity = rigid_body.angvel();
-                    let distance = angle.abs();
+                    let rigid_body = &app.physics_world.rigid_body_set[object.rigid_body];
 
-                    let coefficient = inertia / (distance + 1.0);
+                    app.camera_controller
+                        .add_trauma(damage as f32 * TileMap::TRAUMA_PER_DAMAGE);
 
-                    rigid_body.apply_torque_impulse((angle - velocity) * coefficient, true);
+                    if app.is_player(object) {
+                        app.post_processing.add_damage(damage);
+                    }
+
+                    app.particles.emit_from(
+                        &ParticleStyle::SPARK,
+                        rigid_body,
+                        point,
+                        normal,
+                        (damage * Self::SPARKS_PER_DAMAGE).min(Self::MAX_SPARKS) as f32,
+                        1.0,
+                    );
+
+                    if index.cmpge(Vec2::ZERO).all() && tile_map.damage(index.as_uvec2(), damage) {
+                        explode(app, object.rigid_body, origin + (index + 0.5) * tile_size);
+                    }
+                }
+            }
+            Self::Gun {
+                cooldown,
+                timer,
+                speed,
+                damage,
+                muzzle,
+                energy_cost,
+                heat_cost,
+            } => {
+                *timer = (*timer - App::FIXED_DELTA_TIME).max(0.0);
+
+                if *timer > 0.0
+                    || !app.is_player(object)
+                    || app.keybinds.get(KeyAction::Fire).is_not_pressed()
+                {
+                    return;
+                }
+
+                if let Some(resources) = object.resources_mut() {
+                    if !resources.use_energy(*energy_cost) {
+                        return;
+                    }
+
+                    resources.add_heat(*heat_cost);
+                }
+
+                *timer = *cooldown;
+
+                let rigid_body = &app.physics_world.rigid_body_set[object.rigid_body];
+                let position = rigid_body.position();
+                let rotation = rigid_body.rotation();
+                let direction = vector![rotation.re, rotation.im];
+                let muzzle = position.transform_point(&(*muzzle).into());
+
+                app.particles.emit(
+                    &ParticleStyle::SPARK,
+                    muzzle.into(),
+                    (*rigid_body.linvel()).into(),
+                    direction.into(),
+                    Self::MUZZLE_SPARKS,
+                );
+
+                app.lighting.flash(
+                    Light {
+                        position: muzzle.into(),
+                        color: Self::MUZZLE_FLASH_COLOR,
+                        radius: 24.0,
+                    },
+                    0.08,
+                );
+
+                let projectile = RigidBodyBuilder::dynamic()
+                    .translation(muzzle.coords)
+                    .rotation(rotation.angle())
+                    .linvel(rigid_body.linvel() + direction * *speed)
+                    .ccd_enabled(true)
+                    .build();
+
+                let projectile = Object::new(
+                    &mut app.physics_world,
+                    projectile,
+                    ignore_owner(ColliderBuilder::ball(0.5), object.rigid_body)
+                        .collision_groups(CollisionLayer::Projectile.groups())
+                        .build(),
+                    app.projectile_texture.clone(),
+                    vec![Component::Projectile {
+                        damage: *damage,
+                        lifetime: 2.0,
+                    }],
+                    vec2(0.5, 0.5),
+                );
+
+                app.spawn(projectile);
+            }
+            Self::Projectile { damage, lifetime } => {
+                *lifetime -= App::FIXED_DELTA_TIME;
+
+                let mut hit = None;
+
+                for pair in app
+                    .physics_world
+                    .narrow_phase
+                    .contact_pairs_with(object.collider)
+                {
+                    if !pair.has_any_active_contact {
+                        continue;
+                    }
+
+                    let first = pair.collider1 == object.collider;
+                    let other = if first {
+                        pair.collider2
+                    } else {
+                        pair.collider1
+                    };
+
+                    // The point of contact on the other collider, nudged inside of it
+                    let point = pair.manifolds.first().and_then(|manifold| {
+                        let (point, normal) =
+                            contact_point(manifold, manifold.points.first()?, !first);
+
+                        Some(point - normal * 0.01)
+                    });
+
+                    hit = Some((other, point));
+                    break;
+                }
+
+                if let Some((other, point)) = hit {
+                    let projectile = &app.physics_world.rigid_body_set[object.rigid_body];
+                    app.particles.emit(
+                        &ParticleStyle::SPARK,
+                        (*projectile.translation()).into(),
+                        Vec2::ZERO,
+                        -Vec2::from(*projectile.linvel()),
+                        Self::MAX_SPARKS,
+                    );
+
+                    let target = app
+                        .objects
+                        .iter_mut()
+                        .find(|target| target.collider == other);
+
+                    if let (Some(target), Some(point)) = (target, point) {
+                        let damage = target
+                            .resources_mut()
+                            .map_or(*damage, |resources| resources.shield(*damage));
+
+                        if app.player == Some(target.id) {
+                            app.post_processing.add_damage(damage);
+                        }
+
+                        if let Some(index) = target.local_to_tile(point.into()) {
+                            let center = target.tile_to_local(index).unwrap_or_default()
+                                + target.tile_size().unwrap_or_default() / 2.0;
+
+                            if let Some(tile_map) = target.tile_map_mut() {
+                                if index.cmpge(IVec2::ZERO).all()
+                                    && tile_map.damage(index.as_uvec2(), damage)
+                                {
+                                    let rigid_body = target.rigid_body;
+                                    explode(app, rigid_body, center);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if hit.is_some() || *lifetime <= 0.0 {
+                    app.despawn(object);
+                }
+            }
+            Self::Light {
+                offset: _,
+                color: _,
+                radius: _,
+            } => {}
+            Self::Resources(resources) => {
+                resources.refuelling = touches_tile(app, object, TileType::Refuel);
+                resources.update(App::FIXED_DELTA_TIME);
+            }
+            Self::DockingPort {
+                offset,
+                angle,
+                joint,
+                armed,
+            } => {
+                if let Some(handle) = *joint {
+                    if app.physics_world.joint_connects(handle, object.rigid_body) {
+                        return;
+                    }
+
+                    // Undocked from the other side, or the other object is gone
+                    *joint = None;
+                    *armed = false;
+                }
+
+                let port = *app.get_rigid_body(object).position()
+                    * Isometry::new((*offset).into(), *angle);
+
+                let Some((other, index, other_port)) = nearest_docking_port(app, object, &port)
+                else {
+                    *armed = true;
+                    return;
+                };
+
+                let distance = (other_port.translation.vector - port.translation.vector).norm();
+
+                if !*armed {
+                    *armed = distance > Self::DOCKING_DISTANCE * 2.0;
+                    return;
+                }
+
+                let facing = port.rotation.angle_to(&other_port.rotation).abs();
+                if distance > Self::DOCKING_DISTANCE || PI - facing > Self::DOCKING_ANGLE {
+                    return;
+                }
+
+                let other = &mut app.objects[other];
+                let Self::DockingPort {
+                    offset: other_offset,
+                    angle: other_angle,
+                    joint: other_joint,
+                    armed: _,
+                } = &mut other.components[index]
+                else {
+                    return;
+                };
+
+                // The ports are held facing each other
+                let fixed_joint = FixedJointBuilder::new()
+                    .local_frame1(Isometry::new((*offset).into(), *angle))
+                    .local_frame2(Isometry::new((*other_offset).into(), *other_angle + PI))
+                    .contacts_enabled(false);
+
+                let handle =
+                    app.physics_world
+                        .add_joint(object.rigid_body, other.rigid_body, fixed_joint);
+
+                *joint = Some(handle);
+                *other_joint = Some(handle);
+            }
+            Self::TowCable {
+                anchor: _,
+                length: _,
+                joint,
+            } => {
+                if joint.is_some_and(|joint| {
+                    !app.physics_world.joint_connects(joint, object.rigid_body)
+                }) {
+                    *joint = None;
+                }
+            }
+            Self::ForceField(field) => {
+                let center = Vec2::from(*app.get_rigid_body(object).translation());
+
+                for other in &app.objects {
+                    if other.id == object.id {
+                        continue;
+                    }
+
+                    let rigid_body = &mut app.physics_world.rigid_body_set[other.rigid_body];
+                    if !rigid_body.is_dynamic() {
+                        continue;
+                    }
+
+                    let Some(acceleration) = field.acceleration(
+                        center,
+                        (*rigid_body.center_of_mass()).into(),
+                        (*rigid_body.linvel()).into(),
+                        App::FIXED_DELTA_TIME,
+                    ) else {
+                        continue;
+                    };
+
+                    let impulse = acceleration * rigid_body.mass() * App::FIXED_DELTA_TIME;
+                    rigid_body.apply_impulse(impulse.into(), true);
+                }
+            }
+            Self::TriggerZone {
+                action,
+                player_only,
+                occupants,
+            } => {
+                // Removed objects never get to say they've left
+                occupants.retain(|id| app.objects.iter().any(|object| object.id == *id));
+
+                // The player may also have switched ships since the last tick
+                if let TriggerAction::Door(door) = action {
+                    update_door(app, *door, occupants, *player_only);
                 }
             }
-            Self::TileMap(_) => {}
         }
     }
 
@@ -82,17 +615,153 @@ impl Component {
     pub fn frame_update(&mut self, object: &mut Object, app: &mut App) {
         match self {
             Self::CameraFollow => {
-                app.camera.target = (*app.get_rigid_body(object).center_of_mass()).into();
+                if !app.is_player(object) {
+                    return;
+                }
+
+                let rigid_body = app.get_rigid_body(object);
+                let target = app.interpolated_position(object)
+                    * rigid_body.mass_properties().local_mprops.local_com;
+                let velocity = *rigid_body.linvel();
+
+                app.camera_controller.target = target.coords.into();
+                app.camera_controller.target_velocity = velocity.into();
             }
             Self::Motion {
-                power: _,
-                brake: _,
-                emitter: _,
-            } => {}
+                flight_model,
+                emitter,
+            } => {
+                if !app.is_player(object) {
+                    return;
+                }
+
+                if app.keybinds.get(KeyAction::FlightMode).is_just_pressed() {
+                    flight_model.mode = flight_model.mode.next();
+                }
+
+                if !app.keybinds.get(KeyAction::Boost).is_pressed()
+                    || !object.resources().is_none_or(Resources::can_thrust)
+                {
+                    return;
+                }
+
+                let position = app.interpolated_position(object);
+
+                for exhaust in object.exhaust_positions(*emitter) {
+                    app.lighting.lights.push(Light {
+                        position: (position * Point::from(exhaust)).into(),
+                        color: Self::EXHAUST_COLOR,
+                        radius: 20.0,
+                    });
+                }
+            }
             Self::FaceMouse => {}
             Self::TileMap(tile_map) => {
                 tile_map.update_to_texture(&mut object.texture);
             }
+            Self::Gun {
+                cooldown: _,
+                timer: _,
+                speed: _,
+                damage: _,
+                muzzle: _,
+                energy_cost: _,
+                heat_cost: _,
+            } => {}
+            Self::Projectile {
+                damage: _,
+                lifetime: _,
+            } => {
+                let position = app.interpolated_position(object);
+
+                app.lighting.lights.push(Light {
+                    position: position.translation.vector.into(),
+                    color: Self::MUZZLE_FLASH_COLOR,
+                    radius: 8.0,
+                });
+            }
+            Self::Light {
+                offset,
+                color,
+                radius,
+            } => {
+                let position = app.interpolated_position(object);
+
+                app.lighting.lights.push(Light {
+                    position: (position * Point::from(*offset)).into(),
+                    color: *color,
+                    radius: *radius,
+                });
+            }
+            Self::Resources(_) => {}
+            Self::DockingPort {
+                offset: _,
+                angle: _,
+                joint,
+                armed,
+            } => {
+                if !app.is_player(object) || !app.keybinds.get(KeyAction::Undock).is_just_pressed()
+                {
+                    return;
+                }
+
+                if let Some(handle) = joint.take() {
+                    app.physics_world.remove_joint(handle);
+                    *armed = false;
+                }
+            }
+            Self::TowCable {
+                anchor,
+                length,
+                joint,
+            } => {
+                if !app.is_player(object) || !app.keybinds.get(KeyAction::Tow).is_just_pressed() {
+                    return;
+                }
+
+                if let Some(handle) = joint.take() {
+                    app.physics_world.remove_joint(handle);
+                    return;
+                }
+
+                let start = app.get_rigid_body(object).position() * Point::from(*anchor);
+
+                let nearest = app
+                    .objects
+                    .iter()
+                    .filter(|other| other.id != object.id && !other.is_projectile())
+                    .filter_map(|other| {
+                        let rigid_body = app.get_rigid_body(other);
+                        let distance = (rigid_body.center_of_mass() - start).norm();
+
+                        (rigid_body.is_dynamic() && distance <= *length)
+                            .then_some((other.rigid_body, distance))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+
+                if let Some((rigid_body, _)) = nearest {
+                    let center_of_mass = app.physics_world.rigid_body_set[rigid_body]
+                        .mass_properties()
+                        .local_mprops
+                        .local_com;
+
+                    let rope_joint = RopeJointBuilder::new(*length)
+                        .local_anchor1((*anchor).into())
+                        .local_anchor2(center_of_mass);
+
+                    *joint = Some(app.physics_world.add_joint(
+                        object.rigid_body,
+                        rigid_body,
+                        rope_joint,
+                    ));
+                }
+            }
+            Self::ForceField(_) => {}
+            Self::TriggerZone {
+                action: _,
+                player_only: _,
+                occupants: _,
+            } => {}
         }
     }
 
@@ -102,27 +771,518 @@ impl Component {
         match self {
             Self::CameraFollow => {}
             Self::Motion {
-                power: _,
-                brake: _,
-                emitter,
+                flight_model: _,
+                emitter: _,
+            } => {}
+            Self::FaceMouse => {}
+            Self::TileMap(_) => {}
+            Self::Gun {
+                cooldown: _,
+                timer: _,
+                speed: _,
+                damage: _,
+                muzzle: _,
+                energy_cost: _,
+                heat_cost: _,
+            } => {}
+            Self::Projectile {
+                damage: _,
+                lifetime: _,
+            } => {}
+            Self::Light {
+                offset: _,
+                color: _,
+                radius: _,
+            } => {}
+            Self::Resources(_) => {}
+            Self::DockingPort {
+                offset: _,
+                angle: _,
+                joint: _,
+                armed: _,
+            } => {}
+            Self::TowCable {
+                anchor,
+                length: _,
+                joint,
             } => {
-                const UP: Vec2 = vec2(0.0, 2.0);
-                const LEFT: Vec2 = vec2(0.05, 0.0);
+                let Some(joint) =
+                    joint.and_then(|joint| app.physics_world.impulse_joint_set.get(joint))
+                else {
+                    return;
+                };
 
-                if !app.keybinds.get(KeyAction::Boost).is_pressed() {
+                let Some(other) = app
+                    .objects
+                    .iter()
+                    .find(|other| other.rigid_body == joint.body2)
+                else {
                     return;
-                }
+                };
 
-                let rigid_body = app.get_rigid_body(object);
-                let position = rigid_body.position();
-                let a = position.transform_point(&(*emitter + UP - LEFT).into());
-                let b = position.transform_point(&(*emitter - UP - LEFT).into());
+                let start = app.interpolated_position(object) * Point::from(*anchor);
+                let end = app.interpolated_position(other) * joint.data.local_anchor2();
 
                 gl_use_default_material();
-                draw_line(a.x, a.y, b.x, b.y, 0.1, WHITE);
+                draw_line(start.x, start.y, end.x, end.y, 0.5, Self::CABLE_COLOR);
+            }
+            Self::ForceField(_) => {}
+            Self::TriggerZone {
+                action: _,
+                player_only: _,
+                occupants: _,
+            } => {}
+        }
+    }
+
+    /// Occurs after the physics step when an object enters or leaves a
+    /// trigger zone, for the components of both the zone's object and the
+    /// other object.
+    pub fn on_trigger(&mut self, object: &mut Object, app: &mut App, event: &TriggerEvent) {
+        match self {
+            Self::CameraFollow => {}
+            Self::Motion {
+                flight_model: _,
+                emitter: _,
+            } => {}
+            Self::FaceMouse => {}
+            Self::TileMap(_) => {}
+            Self::Gun {
+                cooldown: _,
+                timer: _,
+                speed: _,
+                damage: _,
+                muzzle: _,
+                energy_cost: _,
+                heat_cost: _,
+            } => {}
+            Self::Projectile {
+                damage: _,
+                lifetime: _,
+            } => {}
+            Self::Light {
+                offset: _,
+                color: _,
+                radius: _,
+            } => {}
+            Self::Resources(_) => {}
+            Self::DockingPort {
+                offset: _,
+                angle: _,
+                joint: _,
+                armed: _,
+            } => {}
+            Self::TowCable {
+                anchor: _,
+                length: _,
+                joint: _,
+            } => {}
+            Self::ForceField(_) => {}
+            Self::TriggerZone {
+                action,
+                player_only,
+                occupants,
+            } => {
+                if event.zone != object.id {
+                    return;
+                }
+
+                match event.kind {
+                    TriggerKind::Enter => occupants.push(event.visitor),
+                    TriggerKind::Exit => occupants.retain(|id| *id != event.visitor),
+                }
+
+                if let TriggerAction::Door(door) = action {
+                    update_door(app, *door, occupants, *player_only);
+                    return;
+                }
+
+                let counts = !*player_only || app.player == Some(event.visitor);
+                if event.kind == TriggerKind::Exit || !counts {
+                    return;
+                }
+
+                match action {
+                    TriggerAction::Checkpoint => {
+                        app.checkpoint = Some(*app.get_rigid_body(object).position());
+                        app.hud.show_message(action.name());
+                    }
+                    TriggerAction::LevelExit => {
+                        app.level_complete = true;
+                        app.hud.show_message(action.name());
+                    }
+                    TriggerAction::Message(text) => app.hud.show_message(text),
+                    TriggerAction::Door(_) => {}
+                }
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CameraFollow => "CameraFollow",
+            Self::Motion { .. } => "Motion",
+            Self::FaceMouse => "FaceMouse",
+            Self::TileMap(_) => "TileMap",
+            Self::Gun { .. } => "Gun",
+            Self::Projectile { .. } => "Projectile",
+            Self::Light { .. } => "Light",
+            Self::Resources(_) => "Resources",
+            Self::DockingPort { .. } => "DockingPort",
+            Self::TowCable { .. } => "TowCable",
+            Self::ForceField(_) => "ForceField",
+            Self::TriggerZone { .. } => "TriggerZone",
+        }
+    }
+
+    /// Shows the fields which can be tuned while playing to `inspect`.
+    /// State which changes by itself, like timers, is left out.
+    pub fn inspect(&mut self, inspect: &mut dyn Inspect) {
+        match self {
+            Self::CameraFollow => {}
+            Self::Motion {
+                flight_model,
+                emitter,
+            } => {
+                inspect.number("max thrust", &mut flight_model.max_thrust, 0.0..30000.0);
+                inspect.number("rcs thrust", &mut flight_model.rcs_thrust, 0.0..12000.0);
+                inspect.number("turn rate", &mut flight_model.turn_rate, 0.0..20.0);
+                inspect.number(
+                    "turn acceleration",
+                    &mut flight_model.turn_acceleration,
+                    0.0..200.0,
+                );
+                inspect.number("fuel use", &mut flight_model.fuel_use, 0.0..10.0);
+                inspect.number(
+                    "brake half life",
+                    &mut flight_model.brake_half_life,
+                    0.05..5.0,
+                );
+                inspect.number(
+                    "grip half life",
+                    &mut flight_model.grip_half_life,
+                    0.01..2.0,
+                );
+                inspect.vector("emitter", emitter, -32.0..32.0);
             }
             Self::FaceMouse => {}
             Self::TileMap(_) => {}
+            Self::Gun {
+                cooldown,
+                timer: _,
+                speed,
+                damage,
+                muzzle,
+                energy_cost,
+                heat_cost,
+            } => {
+                inspect.number("cooldown", cooldown, 0.0..2.0);
+                inspect.number("speed", speed, 0.0..500.0);
+                inspect.integer("damage", damage, 0..200);
+                inspect.vector("muzzle", muzzle, -32.0..32.0);
+                inspect.number("energy cost", energy_cost, 0.0..50.0);
+                inspect.number("heat cost", heat_cost, 0.0..50.0);
+            }
+            Self::Projectile { damage, lifetime } => {
+                inspect.integer("damage", damage, 0..200);
+                inspect.number("lifetime", lifetime, 0.0..10.0);
+            }
+            Self::Light {
+                offset,
+                color,
+                radius,
+            } => {
+                inspect.vector("offset", offset, -128.0..128.0);
+                inspect.color("color", color);
+                inspect.number("radius", radius, 0.0..256.0);
+            }
+            Self::Resources(resources) => {
+                inspect.number("fuel", &mut resources.fuel, 0.0..500.0);
+                inspect.number("fuel capacity", &mut resources.fuel_capacity, 0.0..500.0);
+                inspect.number("refuel rate", &mut resources.refuel_rate, 0.0..100.0);
+                inspect.number("energy", &mut resources.energy, 0.0..500.0);
+                inspect.number(
+                    "energy capacity",
+                    &mut resources.energy_capacity,
+                    0.0..500.0,
+                );
+                inspect.number("energy regen", &mut resources.energy_regen, 0.0..100.0);
+                inspect.number("shield cost", &mut resources.shield_cost, 0.0..5.0);
+                inspect.number("heat capacity", &mut resources.heat_capacity, 0.0..500.0);
+                inspect.number("cooling", &mut resources.cooling, 0.0..100.0);
+                inspect.number("thrust heat", &mut resources.thrust_heat, 0.0..100.0);
+            }
+            Self::DockingPort {
+                offset,
+                angle,
+                joint: _,
+                armed: _,
+            } => {
+                inspect.vector("offset", offset, -128.0..128.0);
+                inspect.number("angle", angle, -PI..PI);
+            }
+            Self::TowCable {
+                anchor,
+                length,
+                joint: _,
+            } => {
+                inspect.vector("anchor", anchor, -32.0..32.0);
+                inspect.number("length", length, 0.0..128.0);
+            }
+            Self::ForceField(field) => {
+                inspect.number("radius", &mut field.radius, 0.0..1000.0);
+
+                match &mut field.kind {
+                    FieldKind::Gravity { strength } => {
+                        inspect.number("strength", strength, 0.0..500000.0);
+                    }
+                    FieldKind::Current { acceleration } => {
+                        inspect.vector("acceleration", acceleration, -200.0..200.0);
+                    }
+                    FieldKind::Nebula { half_life } => {
+                        inspect.number("half life", half_life, 0.1..10.0);
+                    }
+                }
+            }
+            Self::TriggerZone {
+                action: _,
+                player_only,
+                occupants: _,
+            } => {
+                inspect.flag("player only", player_only);
+            }
+        }
+    }
+}
+
+/// Sprays particles and flashes light where a tile was destroyed, given
+/// in the local space of `rigid_body`.
+fn explode(app: &mut App, rigid_body: RigidBodyHandle, center: Vec2) {
+    let rigid_body = &app.physics_world.rigid_body_set[rigid_body];
+
+    app.particles.emit_from(
+        &ParticleStyle::EXPLOSION,
+        rigid_body,
+        center,
+        Vec2::X,
+        Component::EXPLOSION_PARTICLES as f32,
+        1.0,
+    );
+
+    app.lighting.flash(
+        Light {
+            position: (rigid_body.position() * Point::from(center)).into(),
+            color: Component::EXPLOSION_COLOR,
+            radius: 48.0,
+        },
+        0.3,
+    );
+}
+
+/// Whether `object` is touching a tile of `tile_type` in another object's
+/// tile map.
+fn touches_tile(app: &App, object: &Object, tile_type: TileType) -> bool {
+    app.physics_world
+        .narrow_phase
+        .contact_pairs_with(object.collider)
+        .filter(|pair| pair.has_any_active_contact)
+        .any(|pair| {
+            let first = pair.collider1 == object.collider;
+            let other = if first {
+                pair.collider2
+            } else {
+                pair.collider1
+            };
+
+            let Some(other) = app.objects.iter().find(|target| target.collider == other) else {
+                return false;
+            };
+
+            pair.manifolds.iter().any(|manifold| {
+                manifold.points.iter().any(|point| {
+                    // The point of contact on the other collider, nudged
+                    // inside of it
+                    let (point, normal) = contact_point(manifold, point, !first);
+                    let point = point - normal * 0.01;
+
+                    other
+                        .local_to_tile(point.into())
+                        .filter(|index| index.cmpge(IVec2::ZERO).all())
+                        .and_then(|index| other.tile_map()?.get(index.as_uvec2()))
+                        .is_some_and(|tile| tile.tile_type == tile_type)
+                })
+            })
+        })
+}
+
+/// The nearest port on another object which isn't docked to `port`, both
+/// in world space, as the index of its object and component.
+fn nearest_docking_port(
+    app: &App,
+    object: &Object,
+    port: &Isometry<Real>,
+) -> Option<(usize, usize, Isometry<Real>)> {
+    let mut nearest = None;
+    let mut nearest_distance = f32::INFINITY;
+
+    for (object_index, other) in app.objects.iter().enumerate() {
+        if other.id == object.id {
+            continue;
+        }
+
+        let position = app.get_rigid_body(other).position();
+
+        for (index, component) in other.components.iter().enumerate() {
+            let Component::DockingPort {
+                offset,
+                angle,
+                joint: None,
+                armed: _,
+            } = component
+            else {
+                continue;
+            };
+
+            let other_port = position * Isometry::new((*offset).into(), *angle);
+            let distance = (other_port.translation.vector - port.translation.vector).norm();
+
+            if distance < nearest_distance {
+                nearest = Some((object_index, index, other_port));
+                nearest_distance = distance;
+            }
+        }
+    }
+
+    nearest
+}
+
+/// Opens a door while any of a trigger zone's occupants, or only the
+/// player if `player_only`, is inside of the zone, and closes it otherwise.
+fn update_door(app: &mut App, door: ObjectId, occupants: &[ObjectId], player_only: bool) {
+    let open = occupants
+        .iter()
+        .any(|&id| !player_only || app.player == Some(id));
+
+    if let Some(door) = app.objects.iter().find(|object| object.id == door) {
+        let collider = door.collider;
+        app.physics_world.collider_set[collider].set_enabled(!open);
+    }
+}
+
+/// The tiles damaged by contacts with a tile map's collider during the
+/// last step, as the index of each tile, the damage it takes, and the
+/// point and normal of the contact in the collider's local space. Indices
+/// may be out of bounds of the tile map.
+fn collision_damage(
+    narrow_phase: &NarrowPhase,
+    collider: ColliderHandle,
+    tile_size: Vec2,
+    origin: Vec2,
+) -> Vec<(Vec2, u32, Vec2, Vec2)> {
+    let mut damaged = Vec::new();
+
+    for pair in narrow_phase.contact_pairs_with(collider) {
+        let first = pair.collider1 == collider;
+
+        for manifold in &pair.manifolds {
+            for point in &manifold.points {
+                let impulse = point.data.impulse;
+                if impulse <= TileMap::COLLISION_DAMAGE_THRESHOLD {
+                    continue;
+                }
+
+                let (local, normal) = contact_point(manifold, point, first);
+
+                // Nudge the point inside of the tile it's touching
+                let inside = Vec2::from(local - normal * 0.01);
+                let index = ((inside - origin) / tile_size).floor();
+
+                let damage = (impulse - TileMap::COLLISION_DAMAGE_THRESHOLD)
+                    * TileMap::COLLISION_DAMAGE_PER_IMPULSE;
+                damaged.push((index, damage as u32, local.into(), normal.into()));
+            }
+        }
+    }
+
+    damaged
+}
+
+/// A point of contact and the contact normal on the first collider of
+/// `manifold` if `first`, or else on the second, in that collider's local
+/// space. Rapier gives them in the space of the part of a compound shape
+/// which was touched, like a single tile of a tile map.
+fn contact_point(
+    manifold: &ContactManifold,
+    point: &Contact,
+    first: bool,
+) -> (Point<Real>, Vector<Real>) {
+    let (local, normal, subshape_position) = if first {
+        (point.local_p1, manifold.local_n1, manifold.subshape_pos1)
+    } else {
+        (point.local_p2, manifold.local_n2, manifold.subshape_pos2)
+    };
+
+    subshape_position.map_or((local, normal), |position| {
+        (position * local, position * normal)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_world::PhysicsWorld;
+    use crate::tilemap::{Tile, TileType};
+
+    #[test]
+    fn collisions_damage_the_tile_they_hit() {
+        let mut physics_world = PhysicsWorld::new();
+
+        let mut tile_map = TileMap::with_images(uvec2(4, 1), Vec::new());
+        for x in 0..4 {
+            tile_map.set(uvec2(x, 0), Tile::new(TileType::Wall));
+        }
+
+        let tile_size = Vec2::splat(8.0);
+        let shape = tile_map.make_shape(tile_size, Vec2::ZERO).unwrap();
+        let (_, wall) = physics_world.add_rigidbody(
+            RigidBodyBuilder::fixed().build(),
+            ColliderBuilder::new(shape).build(),
+        );
+
+        // Pushed hard against the third tile until it's damaged
+        let (ball, _) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![20.0, 11.0])
+                .build(),
+            ColliderBuilder::ball(2.0).build(),
+        );
+        physics_world.rigid_body_set[ball].add_force(vector![0.0, -500000.0], true);
+
+        let max_health = TileType::Wall.max_health();
+        let health = |tile_map: &TileMap| -> Vec<u8> {
+            (0..4).map(|x| tile_map[uvec2(x, 0)].health).collect()
+        };
+
+        for _ in 0..60 {
+            physics_world.step();
+
+            let damaged =
+                collision_damage(&physics_world.narrow_phase, wall, tile_size, Vec2::ZERO);
+            for (index, damage, _, _) in damaged {
+                if index.cmpge(Vec2::ZERO).all() {
+                    tile_map.damage(index.as_uvec2(), damage);
+                }
+            }
+
+            if health(&tile_map).iter().any(|&health| health < max_health) {
+                break;
+            }
+        }
+
+        let health = health(&tile_map);
+        assert!(health[2] < max_health, "tile healths {:?}", health);
+        for x in [0, 1, 3] {
+            assert_eq!(health[x], max_health, "tile healths {:?}", health);
         }
     }
 }
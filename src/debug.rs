@@ -0,0 +1,489 @@
+use crate::app::App;
+use crate::component::Component;
+use crate::force_field::predict_path;
+use crate::object::Object;
+use crate::physics_world::PhysicsWorld;
+use macroquad::prelude::*;
+use rapier2d::parry::shape::TypedShape;
+use rapier2d::prelude::*;
+
+/// The F3 overlay. Draws every collider, contact, joint and force field
+/// in the physics world over the top of it, with the paths force fields
+/// will pull bodies along, and a panel of frame and physics timings.
+#[derive(Clone, Debug)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    /// How many fixed ticks ran during the last frame.
+    pub ticks_per_frame: u32,
+}
+
+impl DebugOverlay {
+    const AWAKE_COLOR: Color = MAGENTA;
+    const SLEEPING_COLOR: Color = Color::new(0.5, 0.3, 0.5, 1.0);
+    const FIXED_COLOR: Color = SKYBLUE;
+    const SENSOR_COLOR: Color = YELLOW;
+    const DISABLED_COLOR: Color = DARKGRAY;
+    const AABB_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.25);
+    const CONTACT_COLOR: Color = ORANGE;
+    const JOINT_COLOR: Color = LIME;
+    const ORBIT_COLOR: Color = Color::new(0.4, 0.7, 1.0, 0.8);
+    /// How many fixed ticks ahead orbits are predicted.
+    const ORBIT_TICKS: u32 = 600;
+    const PANEL_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.9);
+    const FONT_SIZE: f32 = 20.0;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            ticks_per_frame: 0,
+        }
+    }
+
+    pub fn draw(&self, camera: &Camera2D, physics_world: &PhysicsWorld, objects: &[Object]) {
+        let pixel = pixel_size(camera);
+        let thickness = pixel * LINE_PIXELS;
+
+        for (_, collider) in physics_world.collider_set.iter() {
+            let position = collider.position();
+
+            let aabb = collider.compute_aabb();
+            let size = aabb.extents();
+
+            draw_rectangle_lines(
+                aabb.mins.x,
+                aabb.mins.y,
+                size.x,
+                size.y,
+                thickness,
+                Self::AABB_COLOR,
+            );
+
+            let color = collider_color(physics_world, collider);
+            draw_shape(collider.shape(), position, thickness, color);
+        }
+
+        for object in objects {
+            object.draw_debug(physics_world);
+        }
+
+        draw_contacts(physics_world, pixel);
+        draw_joints(physics_world, thickness);
+
+        for object in objects {
+            let center = (*physics_world.rigid_body_set[object.rigid_body].translation()).into();
+
+            for component in &object.components {
+                if let Component::ForceField(field) = component {
+                    field.draw_debug(center, thickness);
+                }
+            }
+        }
+
+        draw_orbits(physics_world, objects, thickness);
+
+        set_default_camera();
+        self.draw_panel(physics_world);
+        set_camera(camera);
+    }
+
+    /// Frame and physics statistics in the top left corner of the screen.
+    /// Timings are of the last physics step, in milliseconds.
+    fn draw_panel(&self, physics_world: &PhysicsWorld) {
+        let counters = &physics_world.physics_pipeline.counters;
+
+        let awake = physics_world.island_manager.active_dynamic_bodies().len();
+        let (contact_pairs, contact_points) = physics_world
+            .narrow_phase
+            .contact_pairs()
+            .filter(|pair| pair.has_any_active_contact)
+            .fold((0, 0), |(pairs, points), pair| {
+                let pair_points: usize = pair
+                    .manifolds
+                    .iter()
+                    .map(|manifold| manifold.data.solver_contacts.len())
+                    .sum();
+
+                (pairs + 1, points + pair_points)
+            });
+
+        let lines = [
+            format!(
+                "FPS {:4}  frame {:5.1} ms",
+                get_fps(),
+                get_frame_time() * 1000.0
+            ),
+            format!("ticks/frame {}", self.ticks_per_frame),
+            format!(
+                "bodies {} ({} awake)  colliders {}  joints {}",
+                physics_world.rigid_body_set.len(),
+                awake,
+                physics_world.collider_set.len(),
+                physics_world.impulse_joint_set.len(),
+            ),
+            format!(
+                "contacts {} pairs, {} points",
+                contact_pairs, contact_points
+            ),
+            format!("step       {:6.3} ms", counters.step_time()),
+            format!(
+                "  collision {:6.3} ms (broad {:.3}, narrow {:.3})",
+                counters.collision_detection_time(),
+                counters.broad_phase_time(),
+                counters.narrow_phase_time(),
+            ),
+            format!("  islands   {:6.3} ms", counters.island_construction_time()),
+            format!("  solver    {:6.3} ms", counters.solver_time()),
+            format!("  ccd       {:6.3} ms", counters.ccd_time()),
+            format!("  update    {:6.3} ms", counters.update_time()),
+        ];
+
+        let line_height = Self::FONT_SIZE;
+        let area = Rect::new(10.0, 10.0, 440.0, line_height * lines.len() as f32 + 12.0);
+
+        draw_rectangle(
+            area.x,
+            area.y,
+            area.w,
+            area.h,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(
+                line,
+                area.x + 8.0,
+                area.y + line_height * (i + 1) as f32,
+                Self::FONT_SIZE,
+                Self::PANEL_COLOR,
+            );
+        }
+    }
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Colliders on sleeping bodies are dimmed, so it's clear which parts of
+/// the world the solver is skipping.
+fn collider_color(physics_world: &PhysicsWorld, collider: &Collider) -> Color {
+    if !collider.is_enabled() {
+        return DebugOverlay::DISABLED_COLOR;
+    }
+
+    if collider.is_sensor() {
+        return DebugOverlay::SENSOR_COLOR;
+    }
+
+    let Some(rigid_body) = collider
+        .parent()
+        .and_then(|parent| physics_world.rigid_body_set.get(parent))
+    else {
+        return DebugOverlay::FIXED_COLOR;
+    };
+
+    if !rigid_body.is_dynamic() {
+        DebugOverlay::FIXED_COLOR
+    } else if rigid_body.is_sleeping() {
+        DebugOverlay::SLEEPING_COLOR
+    } else {
+        DebugOverlay::AWAKE_COLOR
+    }
+}
+
+/// How wide overlay lines are in pixels, whatever the zoom.
+pub const LINE_PIXELS: f32 = 1.5;
+
+/// The width of a screen pixel in world units when drawing with `camera`.
+pub fn pixel_size(camera: &Camera2D) -> f32 {
+    2.0 / (camera.zoom.x.abs() * screen_width())
+}
+
+/// Draws a path from `start` through each of `points`, fading out
+/// towards its end. Dashed paths leave a gap every few points.
+pub fn draw_fading_path(start: Vec2, points: &[Vec2], thickness: f32, color: Color, dashed: bool) {
+    let mut previous = start;
+
+    for (index, &point) in points.iter().enumerate() {
+        if !dashed || index % 6 < 4 {
+            let color = Color {
+                a: color.a * (1.0 - index as f32 / points.len() as f32),
+                ..color
+            };
+
+            draw_line(previous.x, previous.y, point.x, point.y, thickness, color);
+        }
+
+        previous = point;
+    }
+}
+
+/// Draws the outline of any shape rapier supports. Shapes without an
+/// outline of their own, such as custom shapes, are drawn as their AABB.
+pub fn draw_shape(shape: &dyn Shape, position: &Isometry<Real>, thickness: f32, color: Color) {
+    let transform = |points: &[Point<Real>]| -> Vec<Vec2> {
+        points
+            .iter()
+            .map(|point| (position * point).into())
+            .collect()
+    };
+
+    match shape.as_typed_shape() {
+        TypedShape::Ball(ball) => {
+            let center = Vec2::from(position.translation.vector);
+            let edge = Vec2::from(position * point![ball.radius, 0.0]);
+
+            draw_circle_lines(center.x, center.y, ball.radius, thickness, color);
+            draw_line(center.x, center.y, edge.x, edge.y, thickness, color);
+        }
+        TypedShape::Cuboid(cuboid) => {
+            draw_polygon_lines(&transform(&cuboid.to_polyline()), thickness, color);
+        }
+        TypedShape::RoundCuboid(round) => {
+            let points = transform(&round.inner_shape.to_polyline());
+            draw_rounded_polygon_lines(&points, round.border_radius, thickness, color);
+        }
+        TypedShape::Capsule(capsule) => {
+            let points = transform(&[capsule.segment.a, capsule.segment.b]);
+            draw_rounded_polygon_lines(&points, capsule.radius, thickness, color);
+        }
+        TypedShape::Segment(segment) => {
+            draw_polygon_lines(&transform(&[segment.a, segment.b]), thickness, color);
+        }
+        TypedShape::Triangle(triangle) => {
+            draw_polygon_lines(&transform(triangle.vertices()), thickness, color);
+        }
+        TypedShape::RoundTriangle(round) => {
+            let points = transform(round.inner_shape.vertices());
+            draw_rounded_polygon_lines(&points, round.border_radius, thickness, color);
+        }
+        TypedShape::ConvexPolygon(polygon) => {
+            draw_polygon_lines(&transform(polygon.points()), thickness, color);
+        }
+        TypedShape::RoundConvexPolygon(round) => {
+            let points = transform(round.inner_shape.points());
+            draw_rounded_polygon_lines(&points, round.border_radius, thickness, color);
+        }
+        TypedShape::Polyline(polyline) => {
+            for segment in polyline.segments() {
+                draw_polygon_lines(&transform(&[segment.a, segment.b]), thickness, color);
+            }
+        }
+        TypedShape::HeightField(height_field) => {
+            for segment in height_field.segments() {
+                draw_polygon_lines(&transform(&[segment.a, segment.b]), thickness, color);
+            }
+        }
+        TypedShape::TriMesh(tri_mesh) => {
+            for triangle in tri_mesh.triangles() {
+                draw_polygon_lines(&transform(triangle.vertices()), thickness, color);
+            }
+        }
+        TypedShape::HalfSpace(half_space) => {
+            // Half spaces are infinite, so this draws a long stretch of
+            // their boundary with a tick showing which side is solid
+            let normal = Vec2::from(position * half_space.normal.into_inner());
+            let center = Vec2::from(position.translation.vector);
+            let along = normal.perp() * 10_000.0;
+            let inside = center - normal;
+
+            draw_line(
+                center.x - along.x,
+                center.y - along.y,
+                center.x + along.x,
+                center.y + along.y,
+                thickness,
+                color,
+            );
+            draw_line(center.x, center.y, inside.x, inside.y, thickness, color);
+        }
+        TypedShape::Compound(compound) => {
+            for (shape_position, shape) in compound.shapes() {
+                draw_shape(&**shape, &(position * shape_position), thickness, color);
+            }
+        }
+        _ => {
+            let aabb = shape.compute_aabb(position);
+            let size = aabb.extents();
+
+            draw_rectangle_lines(aabb.mins.x, aabb.mins.y, size.x, size.y, thickness, color);
+        }
+    }
+}
+
+/// Draws lines between each point and the next, closing the loop if
+/// there are more than two.
+fn draw_polygon_lines(points: &[Vec2], thickness: f32, color: Color) {
+    let edges = if points.len() > 2 {
+        points.len()
+    } else {
+        points.len().saturating_sub(1)
+    };
+
+    for i in 0..edges {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+
+        draw_line(a.x, a.y, b.x, b.y, thickness, color);
+    }
+}
+
+/// A convex polygon grown outwards by `radius`, drawn as its inner
+/// polygon plus a rounded border. Two points make a capsule.
+fn draw_rounded_polygon_lines(points: &[Vec2], radius: f32, thickness: f32, color: Color) {
+    const ARC_SEGMENTS: usize = 6;
+
+    draw_polygon_lines(
+        points,
+        thickness,
+        Color {
+            a: color.a * 0.4,
+            ..color
+        },
+    );
+
+    let center = points.iter().sum::<Vec2>() / points.len() as f32;
+    let outward = |a: Vec2, b: Vec2| {
+        let normal = (b - a).perp().normalize_or_zero();
+
+        if normal.dot((a + b) / 2.0 - center) < 0.0 {
+            -normal
+        } else {
+            normal
+        }
+    };
+
+    // Two points have two edges, one facing each way
+    let edges: Vec<(Vec2, Vec2, Vec2)> = if points.len() == 2 {
+        let normal = (points[1] - points[0]).perp().normalize_or_zero();
+        vec![
+            (points[0], points[1], normal),
+            (points[1], points[0], -normal),
+        ]
+    } else {
+        (0..points.len())
+            .map(|i| {
+                let a = points[i];
+                let b = points[(i + 1) % points.len()];
+                (a, b, outward(a, b))
+            })
+            .collect()
+    };
+
+    for i in 0..edges.len() {
+        let (a, b, normal) = edges[i];
+        let (start, end) = (a + normal * radius, b + normal * radius);
+        draw_line(start.x, start.y, end.x, end.y, thickness, color);
+
+        // The corner joining this edge to the next
+        let next_normal = edges[(i + 1) % edges.len()].2;
+        let from = normal.y.atan2(normal.x);
+        let mut sweep = normal.angle_between(next_normal);
+
+        // The arc goes around the outside, which for a capsule's half
+        // turns could be either way
+        if Vec2::from_angle(from + sweep / 2.0).dot(b - center) < 0.0 {
+            sweep -= std::f32::consts::TAU.copysign(sweep);
+        }
+
+        let mut previous = b + Vec2::from_angle(from) * radius;
+        for step in 1..=ARC_SEGMENTS {
+            let angle = from + sweep * step as f32 / ARC_SEGMENTS as f32;
+            let point = b + Vec2::from_angle(angle) * radius;
+
+            draw_line(previous.x, previous.y, point.x, point.y, thickness, color);
+            previous = point;
+        }
+    }
+}
+
+/// Every active contact point with its normal, which points from the
+/// first collider of the pair to the second.
+fn draw_contacts(physics_world: &PhysicsWorld, pixel: f32) {
+    for pair in physics_world.narrow_phase.contact_pairs() {
+        if !pair.has_any_active_contact {
+            continue;
+        }
+
+        for manifold in &pair.manifolds {
+            let normal = Vec2::from(manifold.data.normal);
+
+            for contact in &manifold.data.solver_contacts {
+                let point = Vec2::from(contact.point);
+                let end = point + normal * pixel * 12.0;
+
+                draw_circle(point.x, point.y, pixel * 3.0, DebugOverlay::CONTACT_COLOR);
+                draw_line(
+                    point.x,
+                    point.y,
+                    end.x,
+                    end.y,
+                    pixel * LINE_PIXELS,
+                    DebugOverlay::CONTACT_COLOR,
+                );
+            }
+        }
+    }
+}
+
+/// The path of each dynamic body which is inside of a force field, fading
+/// out into the future.
+fn draw_orbits(physics_world: &PhysicsWorld, objects: &[Object], thickness: f32) {
+    for object in objects {
+        let rigid_body = &physics_world.rigid_body_set[object.rigid_body];
+        if !rigid_body.is_dynamic() || object.is_projectile() {
+            continue;
+        }
+
+        let start = Vec2::from(*rigid_body.center_of_mass());
+        let velocity = Vec2::from(*rigid_body.linvel());
+
+        let path = predict_path(
+            physics_world,
+            objects,
+            Some(object.id),
+            start,
+            velocity,
+            DebugOverlay::ORBIT_TICKS,
+            App::FIXED_DELTA_TIME,
+        );
+
+        // Bodies outside of every field just move in a straight line
+        let straight = start + velocity * App::FIXED_DELTA_TIME * path.len() as f32;
+        if path.last().is_none_or(|end| end.distance(straight) < 0.01) {
+            continue;
+        }
+
+        draw_fading_path(start, &path, thickness, DebugOverlay::ORBIT_COLOR, false);
+    }
+}
+
+/// Each joint as lines from the center of its bodies to their anchors,
+/// with a line between the anchors showing how far the joint has
+/// stretched.
+fn draw_joints(physics_world: &PhysicsWorld, thickness: f32) {
+    let rigid_body_set = &physics_world.rigid_body_set;
+
+    for (_, joint) in physics_world.impulse_joint_set.iter() {
+        let (Some(body1), Some(body2)) = (
+            rigid_body_set.get(joint.body1),
+            rigid_body_set.get(joint.body2),
+        ) else {
+            continue;
+        };
+
+        let center1 = Vec2::from(*body1.translation());
+        let center2 = Vec2::from(*body2.translation());
+        let anchor1 = Vec2::from(body1.position() * joint.data.local_frame1 * Point::origin());
+        let anchor2 = Vec2::from(body2.position() * joint.data.local_frame2 * Point::origin());
+
+        let color = DebugOverlay::JOINT_COLOR;
+        draw_line(center1.x, center1.y, anchor1.x, anchor1.y, thickness, color);
+        draw_line(center2.x, center2.y, anchor2.x, anchor2.y, thickness, color);
+        draw_line(anchor1.x, anchor1.y, anchor2.x, anchor2.y, thickness, RED);
+        draw_circle(anchor1.x, anchor1.y, thickness * 2.0, color);
+        draw_circle(anchor2.x, anchor2.y, thickness * 2.0, color);
+    }
+}
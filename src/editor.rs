@@ -0,0 +1,322 @@
+use crate::component::Component;
+use crate::history::{Command, History, ObjectSnapshot};
+use crate::keybinds::{KeyAction, Keybinds};
+use crate::level::Level;
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use crate::tilemap::{Tile, TileType};
+use macroquad::prelude::*;
+use rapier2d::prelude::*;
+
+/// Paints tiles onto the tile maps of objects with the mouse. The left
+/// mouse button paints the selected tile type and the right mouse
+/// button erases. Objects under the mouse can also be deleted or
+/// duplicated. Every change is recorded in the app's `History`.
+#[derive(Clone, Debug)]
+pub struct Editor {
+    pub enabled: bool,
+    pub brush_size: u32,
+    pub tile_type: TileType,
+    stroke: Option<Stroke>,
+}
+
+/// Tiles painted while a mouse button is held, which are grouped into
+/// a single undo step.
+#[derive(Clone, Debug)]
+struct Stroke {
+    object: usize,
+    tile: Tile,
+    rectangle_start: Option<IVec2>,
+}
+
+impl Editor {
+    pub const MAX_BRUSH_SIZE: u32 = 8;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            brush_size: 1,
+            tile_type: TileType::Wall,
+            stroke: None,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        keybinds: &Keybinds,
+        camera: &Camera2D,
+        history: &mut History,
+        physics_world: &mut PhysicsWorld,
+        objects: &mut Vec<Object>,
+        level_objects: &[ObjectId],
+    ) {
+        if keybinds.get(KeyAction::EditorBrushLarger).is_just_pressed() {
+            self.brush_size = (self.brush_size + 1).min(Self::MAX_BRUSH_SIZE);
+        }
+
+        if keybinds
+            .get(KeyAction::EditorBrushSmaller)
+            .is_just_pressed()
+        {
+            self.brush_size = self.brush_size.saturating_sub(1).max(1);
+        }
+
+        if keybinds.get(KeyAction::EditorNextTile).is_just_pressed() {
+            self.tile_type = self.tile_type.next_placeable();
+        }
+
+        let mouse = camera.screen_to_world(mouse_position().into());
+
+        if self.stroke.is_none() {
+            if keybinds.get(KeyAction::Undo).is_just_pressed() {
+                history.undo(physics_world, objects);
+            }
+
+            if keybinds.get(KeyAction::Redo).is_just_pressed() {
+                history.redo(physics_world, objects);
+            }
+
+            if keybinds.get(KeyAction::EditorDelete).is_just_pressed() {
+                if let Some(index) = Self::object_under(physics_world, objects, mouse) {
+                    let snapshot = ObjectSnapshot::new(&objects[index], physics_world);
+                    history.execute(
+                        Command::DeleteObject { index, snapshot },
+                        physics_world,
+                        objects,
+                    );
+                }
+            }
+
+            if keybinds.get(KeyAction::EditorDuplicate).is_just_pressed() {
+                if let Some(index) = Self::object_under(physics_world, objects, mouse) {
+                    let object = &objects[index];
+                    let mut snapshot = ObjectSnapshot::new(object, physics_world);
+                    snapshot.id = ObjectId::new();
+
+                    let translation =
+                        snapshot.rigid_body.translation() + vector![object.size.x, 0.0];
+                    snapshot.rigid_body.set_translation(translation, false);
+
+                    // Tile maps draw onto their texture, so the copy needs its own
+                    for component in &mut snapshot.components {
+                        if let Component::TileMap(tile_map) = component {
+                            let size = snapshot.texture.size();
+                            snapshot.texture = Texture2D::from_image(&Image::gen_image_color(
+                                size.x as u16,
+                                size.y as u16,
+                                BLANK,
+                            ));
+                            tile_map.update_all();
+                        }
+                    }
+
+                    history.execute(
+                        Command::SpawnObject {
+                            index: objects.len(),
+                            snapshot,
+                        },
+                        physics_world,
+                        objects,
+                    );
+                }
+            }
+        }
+
+        if keybinds.get(KeyAction::Save).is_just_pressed() {
+            match Level::from_objects(physics_world, objects, level_objects).save(Level::PATH) {
+                Ok(()) => info!("Saved level to {}", Level::PATH),
+                Err(error) => error!("Failed to save level: {}", error),
+            }
+        }
+
+        if self.stroke.is_none() {
+            let tile = if is_mouse_button_pressed(MouseButton::Left) {
+                Some(Tile::new(self.tile_type))
+            } else if is_mouse_button_pressed(MouseButton::Right) {
+                Some(Tile::EMPTY)
+            } else {
+                None
+            };
+
+            if let Some(tile) = tile {
+                if let Some((object, index)) = Self::tile_under(physics_world, objects, mouse) {
+                    let rectangle = keybinds.get(KeyAction::EditorRectangle).is_pressed();
+
+                    self.stroke = Some(Stroke {
+                        object,
+                        tile,
+                        rectangle_start: rectangle.then_some(index),
+                    });
+                    history.begin_group();
+                }
+            }
+        }
+
+        let Some(stroke) = &mut self.stroke else {
+            return;
+        };
+
+        let object = &mut objects[stroke.object];
+        let Some(index) = object.world_to_tile(physics_world, mouse) else {
+            return;
+        };
+
+        match stroke.rectangle_start {
+            Some(start) => {
+                if is_mouse_button_released(MouseButton::Left)
+                    || is_mouse_button_released(MouseButton::Right)
+                {
+                    stroke.paint(history, object, start.min(index), start.max(index));
+                }
+            }
+            None => {
+                let (min, max) = brush_bounds(self.brush_size, index);
+                stroke.paint(history, object, min, max);
+            }
+        }
+
+        if !is_mouse_button_down(MouseButton::Left) && !is_mouse_button_down(MouseButton::Right) {
+            self.stroke = None;
+            history.end_group();
+        }
+    }
+
+    pub fn draw(&self, camera: &Camera2D, physics_world: &PhysicsWorld, objects: &[Object]) {
+        let mouse = camera.screen_to_world(mouse_position().into());
+
+        let hovered = match &self.stroke {
+            Some(stroke) => objects[stroke.object]
+                .world_to_tile(physics_world, mouse)
+                .map(|index| (stroke.object, index)),
+            None => Self::tile_under(physics_world, objects, mouse),
+        };
+
+        if let Some((object, index)) = hovered {
+            let (min, max) = match self
+                .stroke
+                .as_ref()
+                .and_then(|stroke| stroke.rectangle_start)
+            {
+                Some(start) => (start.min(index), start.max(index)),
+                None => brush_bounds(self.brush_size, index),
+            };
+
+            draw_tile_outline(&objects[object], physics_world, min, max + 1, YELLOW);
+        }
+
+        set_default_camera();
+        draw_text(
+            &format!(
+                "EDITOR  tile: {:?}  brush: {}",
+                self.tile_type, self.brush_size
+            ),
+            10.0,
+            30.0,
+            30.0,
+            YELLOW,
+        );
+        set_camera(camera);
+    }
+
+    /// Finds the object with a collider under `point`.
+    fn object_under(
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        point: Vec2,
+    ) -> Option<usize> {
+        let collider = physics_world.collider_at(point.into())?;
+        objects
+            .iter()
+            .position(|object| object.collider == collider)
+    }
+
+    /// Finds the first object with a tile map that has a tile under
+    /// `point`.
+    fn tile_under(
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        point: Vec2,
+    ) -> Option<(usize, IVec2)> {
+        objects.iter().enumerate().find_map(|(i, object)| {
+            let index = object.world_to_tile(physics_world, point)?;
+
+            if index.cmplt(IVec2::ZERO).any() {
+                return None;
+            }
+
+            object.tile_map()?.get(index.as_uvec2())?;
+            Some((i, index))
+        })
+    }
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stroke {
+    /// Sets every tile in the inclusive range from `min` to `max`,
+    /// skipping those outside of the tile map.
+    fn paint(&self, history: &mut History, object: &mut Object, min: IVec2, max: IVec2) {
+        let id = object.id;
+        let tile_map = object
+            .tile_map_mut()
+            .expect("Strokes should only be made on objects with tile maps");
+
+        let min = min.max(IVec2::ZERO);
+        let max = max.min(tile_map.size().as_ivec2() - 1);
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let index = uvec2(x as u32, y as u32);
+                let old = tile_map[index];
+
+                if old != self.tile {
+                    tile_map.set(index, self.tile);
+
+                    history.push(Command::SetTile {
+                        object: id,
+                        index,
+                        old,
+                        new: self.tile,
+                    });
+                }
+            }
+        }
+
+        tile_map.reset_hull();
+    }
+}
+
+/// The inclusive range of tiles covered by a square brush.
+fn brush_bounds(size: u32, center: IVec2) -> (IVec2, IVec2) {
+    let size = size as i32;
+    let min = center - IVec2::splat((size - 1) / 2);
+
+    (min, min + IVec2::splat(size - 1))
+}
+
+/// Outlines the tiles from `min` up to but not including `max`.
+fn draw_tile_outline(
+    object: &Object,
+    physics_world: &PhysicsWorld,
+    min: IVec2,
+    max: IVec2,
+    color: Color,
+) {
+    let position = physics_world.rigid_body_set[object.rigid_body].position();
+
+    let corners = [min, ivec2(max.x, min.y), max, ivec2(min.x, max.y)].map(|corner| {
+        let local = object.tile_to_local(corner).unwrap();
+        Vec2::from(position.transform_point(&local.into()))
+    });
+
+    for i in 0..corners.len() {
+        let a = corners[i];
+        let b = corners[(i + 1) % corners.len()];
+
+        draw_line(a.x, a.y, b.x, b.y, 0.25, color);
+    }
+}
@@ -0,0 +1,306 @@
+use macroquad::prelude::*;
+use nalgebra::{Complex, Unit};
+use rapier2d::prelude::*;
+
+/// How a ship's engines respond to the pilot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlightMode {
+    /// Thrust only ever changes velocity, so ships drift until thrust is
+    /// applied the other way. The RCS thrusters strafe and fire
+    /// retro-thrust.
+    Newtonian,
+    /// Newtonian, except the RCS thrusters also cancel any drift sideways
+    /// from where the ship is facing.
+    FlightAssist,
+    /// Velocity follows where the ship faces, and slowing down brakes
+    /// instead of thrusting backwards.
+    Arcade,
+}
+
+impl FlightMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Newtonian => Self::FlightAssist,
+            Self::FlightAssist => Self::Arcade,
+            Self::Arcade => Self::Newtonian,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Newtonian => "NEWTONIAN",
+            Self::FlightAssist => "ASSIST",
+            Self::Arcade => "ARCADE",
+        }
+    }
+}
+
+/// The handling of a ship class, in physical units so that ships handle
+/// the same whatever the length of a fixed tick.
+#[derive(Clone, Debug)]
+pub struct FlightModel {
+    pub mode: FlightMode,
+    /// Force from the main engine, or from each thruster tile.
+    pub max_thrust: f32,
+    /// Force from the RCS thrusters when strafing, retro-thrusting or
+    /// cancelling drift.
+    pub rcs_thrust: f32,
+    /// The fastest the ship turns in radians per second.
+    pub turn_rate: f32,
+    /// How quickly the ship reaches its turn rate in radians per second
+    /// squared.
+    pub turn_acceleration: f32,
+    /// Fuel burned per second by the main engine at full thrust. The RCS
+    /// thrusters burn fuel in proportion to their thrust.
+    pub fuel_use: f32,
+    /// Arcade only: seconds for the ship's speed to halve while slowing.
+    pub brake_half_life: f32,
+    /// Arcade only: seconds for half of the difference between the ship's
+    /// velocity and its facing to be turned away.
+    pub grip_half_life: f32,
+}
+
+/// What the pilot is asking the engines for this tick.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct FlightInput {
+    pub thrust: bool,
+    pub retro: bool,
+    /// From -1 for left to 1 for right.
+    pub strafe: f32,
+}
+
+/// The impulses the engines apply this tick.
+#[derive(Clone, Copy, Debug)]
+pub struct FlightOutput {
+    /// Applied by the main engine or at each thruster tile.
+    pub main: Vector<Real>,
+    /// Applied at the center of mass.
+    pub rcs: Vector<Real>,
+    /// How hard each main engine is working, where 1 is full thrust.
+    pub main_burn: f32,
+    /// How hard the RCS thrusters are working, compared to one main
+    /// engine. Arcade grip and braking are free.
+    pub rcs_burn: f32,
+}
+
+impl FlightOutput {
+    /// How hard all of the engines are working together, where 1 is one
+    /// main engine at full thrust. `engines` is the number of main
+    /// engines, one for each thruster tile.
+    pub fn burn(&self, engines: usize) -> f32 {
+        self.main_burn * engines as f32 + self.rcs_burn
+    }
+}
+
+impl FlightModel {
+    pub const NEWTONIAN: Self = Self {
+        mode: FlightMode::Newtonian,
+        max_thrust: 6000.0,
+        rcs_thrust: 1800.0,
+        turn_rate: 6.0,
+        turn_acceleration: 40.0,
+        fuel_use: 1.0,
+        brake_half_life: 0.45,
+        grip_half_life: 0.11,
+    };
+
+    pub const FLIGHT_ASSIST: Self = Self {
+        mode: FlightMode::FlightAssist,
+        ..Self::NEWTONIAN
+    };
+
+    pub const ARCADE: Self = Self {
+        mode: FlightMode::Arcade,
+        turn_rate: 8.0,
+        turn_acceleration: 80.0,
+        ..Self::NEWTONIAN
+    };
+
+    /// Works out the impulses for `input` over a tick of `delta_time`
+    /// seconds. When the engines can't thrust, such as when out of fuel,
+    /// only arcade braking and grip still work.
+    pub fn update(
+        &self,
+        rigid_body: &RigidBody,
+        input: FlightInput,
+        can_thrust: bool,
+        delta_time: f32,
+    ) -> FlightOutput {
+        let forward = rotation_vector(rigid_body.rotation());
+        let right = vector![-forward.y, forward.x];
+        let velocity = *rigid_body.linvel();
+        let mass = rigid_body.mass();
+
+        let mut main = Vector::zeros();
+        let mut rcs = Vector::zeros();
+
+        if input.thrust && can_thrust {
+            main = forward * self.max_thrust;
+        }
+
+        match self.mode {
+            FlightMode::Newtonian | FlightMode::FlightAssist => {
+                if can_thrust {
+                    if input.retro {
+                        rcs -= forward * self.rcs_thrust;
+                    }
+
+                    rcs += right * input.strafe.clamp(-1.0, 1.0) * self.rcs_thrust;
+                }
+
+                if self.mode == FlightMode::FlightAssist && can_thrust && input.strafe == 0.0 {
+                    let drift = right * velocity.dot(&right);
+                    rcs -= (drift * mass / delta_time).cap_magnitude(self.rcs_thrust);
+                }
+            }
+            FlightMode::Arcade => {
+                if can_thrust {
+                    rcs += right * input.strafe.clamp(-1.0, 1.0) * self.rcs_thrust;
+                }
+
+                // Turns velocity towards the way the ship is facing,
+                // keeping its speed
+                let speed = velocity.norm();
+                let heading = if velocity.dot(&forward) >= 0.0 {
+                    forward
+                } else {
+                    -forward
+                };
+                let mut target = velocity.lerp(
+                    &(heading * speed),
+                    1.0 - decay(self.grip_half_life, delta_time),
+                );
+
+                if input.retro {
+                    target *= decay(self.brake_half_life, delta_time);
+                }
+
+                rcs += (target - velocity) * mass / delta_time;
+            }
+        }
+
+        let main_burn = if main != Vector::zeros() { 1.0 } else { 0.0 };
+        let rcs_burn = match self.mode {
+            FlightMode::Arcade if can_thrust => input.strafe.abs().min(1.0) * self.rcs_thrust,
+            FlightMode::Arcade => 0.0,
+            _ => rcs.norm(),
+        } / self.max_thrust.max(f32::EPSILON);
+
+        FlightOutput {
+            main: main * delta_time,
+            rcs: rcs * delta_time,
+            main_burn,
+            rcs_burn,
+        }
+    }
+
+    /// The angular impulse over a tick of `delta_time` seconds turning the
+    /// ship to face `target`, a direction in world space, without going
+    /// over the turn rate.
+    pub fn turn_impulse(&self, rigid_body: &RigidBody, target: Vec2, delta_time: f32) -> f32 {
+        let target = Unit::<Complex<f32>>::new_normalize(Complex::new(target.x, target.y));
+        let angle = rigid_body.rotation().angle_to(&target);
+
+        if !angle.is_finite() {
+            return 0.0;
+        }
+
+        // The fastest the ship can turn while still being able to stop
+        // on the target
+        let stopping = (2.0 * self.turn_acceleration * angle.abs()).sqrt();
+        let desired = (angle.signum() * stopping).clamp(-self.turn_rate, self.turn_rate);
+
+        let max_change = self.turn_acceleration * delta_time;
+        let change = (desired - rigid_body.angvel()).clamp(-max_change, max_change);
+
+        change * rigid_body.mass_properties().effective_angular_inertia()
+    }
+}
+
+impl Default for FlightModel {
+    fn default() -> Self {
+        Self::FLIGHT_ASSIST
+    }
+}
+
+/// The fraction of something with a half-life of `half_life` seconds left
+/// after `delta_time` seconds.
+pub fn decay(half_life: f32, delta_time: f32) -> f32 {
+    if half_life <= 0.0 {
+        return 0.0;
+    }
+
+    0.5_f32.powf(delta_time / half_life)
+}
+
+fn rotation_vector(rotation: &Rotation<Real>) -> Vector<Real> {
+    vector![rotation.re, rotation.im]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_world::PhysicsWorld;
+
+    const TICK_RATES: [f32; 3] = [30.0, 60.0, 120.0];
+
+    /// How far a ship moving forwards at `speed` travels while holding
+    /// retro until it stops, stepping the world `ticks_per_second` times a
+    /// second.
+    fn stopping_distance(flight_model: &FlightModel, speed: f32, ticks_per_second: f32) -> f32 {
+        let delta_time = 1.0 / ticks_per_second;
+
+        let mut physics_world = PhysicsWorld::new();
+        physics_world.integration_parameters.dt = delta_time;
+
+        let (rigid_body, _) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic()
+                .linvel(vector![speed, 0.0])
+                .can_sleep(false)
+                .build(),
+            ColliderBuilder::ball(4.0).build(),
+        );
+
+        let input = FlightInput {
+            retro: true,
+            ..Default::default()
+        };
+
+        for _ in 0..(ticks_per_second * 60.0) as u32 {
+            let body = &mut physics_world.rigid_body_set[rigid_body];
+
+            // Arcade braking never quite reaches zero
+            if body.linvel().x <= 0.01 {
+                return body.translation().x;
+            }
+
+            let output = flight_model.update(body, input, true, delta_time);
+            body.apply_impulse(output.main + output.rcs, true);
+
+            physics_world.step();
+        }
+
+        panic!("Ship didn't stop within a minute");
+    }
+
+    fn assert_same_at_each_tick_rate(flight_model: &FlightModel) {
+        let distances = TICK_RATES.map(|rate| stopping_distance(flight_model, 50.0, rate));
+
+        for distance in distances {
+            assert!(
+                (distance - distances[1]).abs() < distances[1] * 0.03,
+                "Stopping distances differ: {distances:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn retro_thrust_stops_in_same_distance() {
+        assert_same_at_each_tick_rate(&FlightModel::NEWTONIAN);
+    }
+
+    #[test]
+    fn arcade_brake_stops_in_same_distance() {
+        assert_same_at_each_tick_rate(&FlightModel::ARCADE);
+    }
+}
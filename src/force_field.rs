@@ -0,0 +1,177 @@
+use crate::component::Component;
+use crate::flight_model::decay;
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use macroquad::prelude::*;
+
+/// An environmental force acting on every dynamic body within `radius` of
+/// the object it's attatched to.
+#[derive(Clone, Debug)]
+pub struct ForceField {
+    pub kind: FieldKind,
+    pub radius: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum FieldKind {
+    /// Pulls bodies towards the center, like a planet or black hole, with
+    /// an acceleration of `strength` divided by the square of their
+    /// distance.
+    Gravity { strength: f32 },
+    /// Pushes bodies along `acceleration`, in world space.
+    Current { acceleration: Vec2 },
+    /// Slows bodies moving through it, halving their velocity every
+    /// `half_life` seconds.
+    Nebula { half_life: f32 },
+}
+
+impl ForceField {
+    /// Gravity is worked out as if bodies were never closer than this to
+    /// the center, so that it doesn't fling them away.
+    pub const MIN_GRAVITY_DISTANCE: f32 = 8.0;
+
+    const FIELD_COLOR: Color = Color::new(0.4, 0.7, 1.0, 0.6);
+    const NEBULA_COLOR: Color = Color::new(0.7, 0.4, 1.0, 1.0);
+    /// How many arrows are drawn across the field's radius.
+    const ARROWS: i32 = 6;
+
+    /// The acceleration over a tick of `delta_time` seconds of a body at
+    /// `point` moving at `velocity`, from a field centered at `center`.
+    /// Returns `None` if the body is outside of the field.
+    pub fn acceleration(
+        &self,
+        center: Vec2,
+        point: Vec2,
+        velocity: Vec2,
+        delta_time: f32,
+    ) -> Option<Vec2> {
+        let offset = center - point;
+        if offset.length() > self.radius {
+            return None;
+        }
+
+        Some(match self.kind {
+            FieldKind::Gravity { strength } => {
+                let distance = offset.length().max(Self::MIN_GRAVITY_DISTANCE);
+                offset.normalize_or_zero() * strength / (distance * distance)
+            }
+            FieldKind::Current { acceleration } => acceleration,
+            FieldKind::Nebula { half_life } => {
+                velocity * (decay(half_life, delta_time) - 1.0) / delta_time
+            }
+        })
+    }
+
+    /// Outlines the field centered at `center`, with arrows showing the
+    /// direction and strength of gravity and currents, and nebulae shaded
+    /// by how strong their drag is.
+    pub fn draw_debug(&self, center: Vec2, thickness: f32) {
+        draw_circle_lines(
+            center.x,
+            center.y,
+            self.radius,
+            thickness,
+            Self::FIELD_COLOR,
+        );
+
+        if let FieldKind::Nebula { half_life } = self.kind {
+            // Fully opaque once velocity halves in a tenth of a second
+            let drag = 0.1 / half_life.max(0.1);
+            let color = Color {
+                a: drag * 0.4,
+                ..Self::NEBULA_COLOR
+            };
+
+            draw_circle(center.x, center.y, self.radius, color);
+            return;
+        }
+
+        let spacing = self.radius / Self::ARROWS as f32;
+
+        for y in -Self::ARROWS..=Self::ARROWS {
+            for x in -Self::ARROWS..=Self::ARROWS {
+                let point = center + ivec2(x, y).as_vec2() * spacing;
+                let Some(acceleration) = self.acceleration(center, point, Vec2::ZERO, 1.0) else {
+                    continue;
+                };
+
+                // Arrows reach their full length at an acceleration of 100
+                let strength = (acceleration.length() / 100.0).min(1.0);
+                if strength <= 0.0 {
+                    continue;
+                }
+
+                let tip = point + acceleration.normalize() * spacing * 0.8 * strength.sqrt();
+                let color = Color {
+                    a: 0.2 + strength * 0.8,
+                    ..Self::FIELD_COLOR
+                };
+
+                draw_line(point.x, point.y, tip.x, tip.y, thickness, color);
+                draw_circle(tip.x, tip.y, thickness * 1.5, color);
+            }
+        }
+    }
+}
+
+/// The combined acceleration over a tick of `delta_time` seconds of a
+/// body at `point` moving at `velocity`, from the force fields of every
+/// object other than `except`.
+pub fn field_acceleration(
+    physics_world: &PhysicsWorld,
+    objects: &[Object],
+    except: Option<ObjectId>,
+    point: Vec2,
+    velocity: Vec2,
+    delta_time: f32,
+) -> Vec2 {
+    let mut total = Vec2::ZERO;
+
+    for object in objects {
+        if Some(object.id) == except {
+            continue;
+        }
+
+        let center = Vec2::from(*physics_world.rigid_body_set[object.rigid_body].translation());
+
+        for component in &object.components {
+            if let Component::ForceField(field) = component {
+                total += field
+                    .acceleration(center, point, velocity, delta_time)
+                    .unwrap_or_default();
+            }
+        }
+    }
+
+    total
+}
+
+/// Where a body starting at `position` with `velocity` will be after
+/// each of the next `ticks` ticks of `delta_time` seconds, moved only by
+/// force fields. Collisions and thrust are ignored.
+pub fn predict_path(
+    physics_world: &PhysicsWorld,
+    objects: &[Object],
+    except: Option<ObjectId>,
+    mut position: Vec2,
+    mut velocity: Vec2,
+    ticks: u32,
+    delta_time: f32,
+) -> Vec<Vec2> {
+    (0..ticks)
+        .map(|_| {
+            // Velocity is updated first, like the physics world does
+            velocity += field_acceleration(
+                physics_world,
+                objects,
+                except,
+                position,
+                velocity,
+                delta_time,
+            ) * delta_time;
+            position += velocity * delta_time;
+
+            position
+        })
+        .collect()
+}
@@ -1,6 +1,18 @@
 use macroquad::prelude::*;
 
 pub fn make_tri_pixel_material() -> Material {
+    make_tri_pixel_material_with_blend(miniquad::BlendFactor::OneMinusValue(
+        miniquad::BlendValue::SourceAlpha,
+    ))
+}
+
+/// Draws textures on top of what's beneath them without lighting, for
+/// the parts of sprites which glow.
+pub fn make_emissive_material() -> Material {
+    make_tri_pixel_material_with_blend(miniquad::BlendFactor::One)
+}
+
+fn make_tri_pixel_material_with_blend(destination: miniquad::BlendFactor) -> Material {
     load_material(
         ShaderSource::Glsl {
             vertex: include_str!("shaders/vertex.glsl"),
@@ -11,41 +23,74 @@ pub fn make_tri_pixel_material() -> Material {
                 color_blend: Some(miniquad::BlendState::new(
                     miniquad::Equation::Add,
                     miniquad::BlendFactor::Value(miniquad::BlendValue::SourceAlpha),
-                    miniquad::BlendFactor::OneMinusValue(miniquad::BlendValue::SourceAlpha),
+                    destination,
                 )),
                 ..Default::default()
             },
-            ..Default::default()
+            uniforms: vec![("Lit".to_string(), UniformType::Float1)],
+            textures: vec!["LightMap".to_string()],
         },
     )
     .expect("Shader code should be valid")
 }
 
-pub fn draw_stars_around(center: Vec2) {
-    let center = center.as_dvec2();
-
-    for i in 500..650 {
-        let i = (i * i) as f64;
-        draw_star(
-            dvec2(
-                (i * 34501.0 - center.x) % 1000.0 - 500.0 + center.x,
-                (i * 75683.0 - center.y) % 1000.0 - 500.0 + center.y,
-            ),
-            i * 4001.0,
-        );
+/// Draws with the view of a screen camera, optionally into a render
+/// target. Unlike a `Camera2D` with a render target, the image isn't
+/// flipped, so it lines up with what would've been drawn to the screen.
+#[derive(Clone)]
+pub struct TargetCamera {
+    pub matrix: Mat4,
+    pub render_target: Option<RenderTarget>,
+}
+
+impl TargetCamera {
+    pub fn new(camera: &Camera2D, render_target: Option<RenderTarget>) -> Self {
+        Self {
+            matrix: camera.matrix(),
+            render_target,
+        }
+    }
+
+    /// Maps the unit square onto the whole render target, for drawing
+    /// one target onto another.
+    pub fn full(render_target: Option<RenderTarget>) -> Self {
+        Self {
+            matrix: Mat4::orthographic_rh_gl(0.0, 1.0, 0.0, 1.0, -1.0, 1.0),
+            render_target,
+        }
+    }
+}
+
+impl Camera for TargetCamera {
+    fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+
+    fn depth_enabled(&self) -> bool {
+        false
+    }
+
+    fn render_pass(&self) -> Option<miniquad::RenderPass> {
+        self.render_target
+            .as_ref()
+            .map(|render_target| render_target.render_pass)
+    }
+
+    fn viewport(&self) -> Option<(i32, i32, i32, i32)> {
+        None
     }
 }
 
-fn draw_star(position: DVec2, rotation: f64) {
-    draw_rectangle_ex(
-        position.x as f32,
-        position.y as f32,
-        1.0,
-        1.0,
-        DrawRectangleParams {
-            offset: Vec2::splat(0.5),
-            rotation: rotation as f32,
-            color: WHITE,
+/// Draws a texture over the whole of the current `TargetCamera::full`.
+pub fn draw_full(texture: &Texture2D) {
+    draw_texture_ex(
+        texture,
+        0.0,
+        0.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(Vec2::ONE),
+            ..Default::default()
         },
-    )
+    );
 }
@@ -0,0 +1,392 @@
+use crate::component::Component;
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use crate::tilemap::Tile;
+use macroquad::prelude::*;
+use rapier2d::prelude::*;
+use std::{collections::VecDeque, mem::size_of};
+
+/// Records reversible changes to the world so they can be undone and
+/// redone. Commands are recorded after they have been applied, and are
+/// undone in groups so that a whole drag stroke is undone at once.
+#[derive(Clone)]
+pub struct History {
+    undo_stack: VecDeque<Vec<Command>>,
+    redo_stack: Vec<Vec<Command>>,
+    group: Option<Vec<Command>>,
+    /// The approximate number of bytes the history may use before the
+    /// oldest groups are forgotten.
+    pub memory_budget: usize,
+    memory_used: usize,
+}
+
+impl History {
+    pub const DEFAULT_MEMORY_BUDGET: usize = 16 * 1024 * 1024;
+
+    pub fn new(memory_budget: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            group: None,
+            memory_budget,
+            memory_used: 0,
+        }
+    }
+
+    /// Starts collecting pushed commands into a single undo step, until
+    /// `end_group` is called.
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.group = Some(Vec::new());
+    }
+
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take() {
+            self.push_group(group);
+        }
+    }
+
+    pub fn is_grouping(&self) -> bool {
+        self.group.is_some()
+    }
+
+    /// Records a command which has already been applied to the world.
+    pub fn push(&mut self, command: Command) {
+        match &mut self.group {
+            Some(group) => group.push(command),
+            None => self.push_group(vec![command]),
+        }
+    }
+
+    /// Applies a command to the world then records it.
+    pub fn execute(
+        &mut self,
+        mut command: Command,
+        physics_world: &mut PhysicsWorld,
+        objects: &mut Vec<Object>,
+    ) {
+        command.redo(physics_world, objects);
+        self.push(command);
+    }
+
+    pub fn undo(&mut self, physics_world: &mut PhysicsWorld, objects: &mut Vec<Object>) {
+        self.end_group();
+
+        let Some(mut group) = self.undo_stack.pop_back() else {
+            return;
+        };
+
+        for command in group.iter_mut().rev() {
+            command.undo(physics_world, objects);
+        }
+
+        self.redo_stack.push(group);
+    }
+
+    pub fn redo(&mut self, physics_world: &mut PhysicsWorld, objects: &mut Vec<Object>) {
+        self.end_group();
+
+        let Some(mut group) = self.redo_stack.pop() else {
+            return;
+        };
+
+        for command in &mut group {
+            command.redo(physics_world, objects);
+        }
+
+        self.undo_stack.push_back(group);
+    }
+
+    /// Points commands which set any of `tiles` on the object `from` at
+    /// the object `to` instead, for when those tiles are split off into a
+    /// new object.
+    pub fn move_tiles(&mut self, from: ObjectId, to: ObjectId, tiles: &[UVec2]) {
+        let commands = self
+            .undo_stack
+            .iter_mut()
+            .chain(self.redo_stack.iter_mut())
+            .chain(self.group.iter_mut())
+            .flatten();
+
+        for command in commands {
+            if let Command::SetTile { object, index, .. } = command {
+                if *object == from && tiles.contains(index) {
+                    *object = to;
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group = None;
+        self.memory_used = 0;
+    }
+
+    fn push_group(&mut self, group: Vec<Command>) {
+        if group.is_empty() {
+            return;
+        }
+
+        for redone in self.redo_stack.drain(..) {
+            self.memory_used = self.memory_used.saturating_sub(group_memory_size(&redone));
+        }
+
+        self.memory_used += group_memory_size(&group);
+        self.undo_stack.push_back(group);
+
+        while self.memory_used > self.memory_budget && self.undo_stack.len() > 1 {
+            let forgotten = self.undo_stack.pop_front().unwrap();
+            self.memory_used = self
+                .memory_used
+                .saturating_sub(group_memory_size(&forgotten));
+        }
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MEMORY_BUDGET)
+    }
+}
+
+#[derive(Clone)]
+pub enum Command {
+    SetTile {
+        object: ObjectId,
+        index: UVec2,
+        old: Tile,
+        new: Tile,
+    },
+    /// The object is inserted at `index` when redone. Its snapshot is
+    /// updated whenever it's undone.
+    SpawnObject {
+        index: usize,
+        snapshot: ObjectSnapshot,
+    },
+    /// The object is reinserted at `index` when undone. Its snapshot is
+    /// updated whenever it's redone.
+    DeleteObject {
+        index: usize,
+        snapshot: ObjectSnapshot,
+    },
+    EditComponent {
+        object: ObjectId,
+        component: usize,
+        old: Component,
+        new: Component,
+    },
+}
+
+impl Command {
+    pub fn redo(&mut self, physics_world: &mut PhysicsWorld, objects: &mut Vec<Object>) {
+        match self {
+            Self::SetTile {
+                object,
+                index,
+                old: _,
+                new,
+            } => set_tile(objects, *object, *index, *new),
+            Self::SpawnObject { index, snapshot } => {
+                insert_object(physics_world, objects, *index, snapshot)
+            }
+            Self::DeleteObject { index, snapshot } => {
+                if let Some(removed) = remove_object(physics_world, objects, snapshot.id) {
+                    (*index, *snapshot) = removed;
+                }
+            }
+            Self::EditComponent {
+                object,
+                component,
+                old: _,
+                new,
+            } => set_component(objects, *object, *component, new),
+        }
+    }
+
+    pub fn undo(&mut self, physics_world: &mut PhysicsWorld, objects: &mut Vec<Object>) {
+        match self {
+            Self::SetTile {
+                object,
+                index,
+                old,
+                new: _,
+            } => set_tile(objects, *object, *index, *old),
+            Self::SpawnObject { index, snapshot } => {
+                if let Some(removed) = remove_object(physics_world, objects, snapshot.id) {
+                    (*index, *snapshot) = removed;
+                }
+            }
+            Self::DeleteObject { index, snapshot } => {
+                insert_object(physics_world, objects, *index, snapshot)
+            }
+            Self::EditComponent {
+                object,
+                component,
+                old,
+                new: _,
+            } => set_component(objects, *object, *component, old),
+        }
+    }
+
+    /// An estimate of the memory used by the command, not counting
+    /// textures since they are shared with the world.
+    pub fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + match self {
+                Self::SetTile { .. } => 0,
+                Self::SpawnObject { snapshot, .. } | Self::DeleteObject { snapshot, .. } => {
+                    snapshot.memory_size()
+                }
+                Self::EditComponent { old, new, .. } => {
+                    component_memory_size(old) + component_memory_size(new)
+                }
+            }
+    }
+}
+
+/// Everything needed to recreate an object after it has been removed
+/// from the world.
+#[derive(Clone)]
+pub struct ObjectSnapshot {
+    pub id: ObjectId,
+    pub rigid_body: RigidBody,
+    pub collider: Collider,
+    pub texture: Texture2D,
+    pub emissive: Option<Texture2D>,
+    pub components: Vec<Component>,
+    pub offset: Vec2,
+}
+
+impl ObjectSnapshot {
+    pub fn new(object: &Object, physics_world: &PhysicsWorld) -> Self {
+        Self {
+            id: object.id,
+            rigid_body: physics_world.rigid_body_set[object.rigid_body].clone(),
+            collider: physics_world.collider_set[object.collider].clone(),
+            texture: object.texture.clone(),
+            emissive: object.emissive.clone(),
+            components: object.components.clone(),
+            offset: object.offset,
+        }
+    }
+
+    pub fn restore(&self, physics_world: &mut PhysicsWorld) -> Object {
+        let mut object = Object::new(
+            physics_world,
+            self.rigid_body.clone(),
+            self.collider.clone(),
+            self.texture.clone(),
+            self.components.clone(),
+            self.offset,
+        );
+
+        object.id = self.id;
+        object.emissive = self.emissive.clone();
+
+        object
+    }
+
+    pub fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .components
+                .iter()
+                .map(|component| size_of::<Component>() + component_memory_size(component))
+                .sum::<usize>()
+    }
+}
+
+/// Removes an object from the world, returning its former index and a
+/// snapshot to recreate it with.
+pub fn remove_object(
+    physics_world: &mut PhysicsWorld,
+    objects: &mut Vec<Object>,
+    id: ObjectId,
+) -> Option<(usize, ObjectSnapshot)> {
+    let index = objects.iter().position(|object| object.id == id)?;
+    let snapshot = ObjectSnapshot::new(&objects[index], physics_world);
+
+    let object = objects.remove(index);
+    physics_world.remove_rigidbody(object.rigid_body);
+
+    Some((index, snapshot))
+}
+
+fn insert_object(
+    physics_world: &mut PhysicsWorld,
+    objects: &mut Vec<Object>,
+    index: usize,
+    snapshot: &ObjectSnapshot,
+) {
+    let object = snapshot.restore(physics_world);
+    objects.insert(index.min(objects.len()), object);
+}
+
+fn find_object(objects: &mut [Object], id: ObjectId) -> Option<&mut Object> {
+    objects.iter_mut().find(|object| object.id == id)
+}
+
+fn set_tile(objects: &mut [Object], id: ObjectId, index: UVec2, tile: Tile) {
+    if let Some(tile_map) = find_object(objects, id).and_then(Object::tile_map_mut) {
+        tile_map.set(index, tile);
+        tile_map.reset_hull();
+    }
+}
+
+fn set_component(objects: &mut [Object], id: ObjectId, index: usize, value: &Component) {
+    if let Some(component) =
+        find_object(objects, id).and_then(|object| object.components.get_mut(index))
+    {
+        *component = value.clone();
+    }
+}
+
+fn component_memory_size(component: &Component) -> usize {
+    match component {
+        Component::TileMap(tile_map) => {
+            tile_map.contents.len() * tile_map.contents[0].len() * size_of::<Tile>()
+        }
+        _ => 0,
+    }
+}
+
+fn group_memory_size(group: &[Command]) -> usize {
+    group.iter().map(Command::memory_size).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::TileType;
+
+    #[test]
+    fn moved_tiles_are_set_on_their_new_object() {
+        let mut history = History::default();
+        let (from, to) = (ObjectId::new(), ObjectId::new());
+
+        for x in 0..2 {
+            history.push(Command::SetTile {
+                object: from,
+                index: uvec2(x, 0),
+                old: Tile::EMPTY,
+                new: Tile::new(TileType::Wall),
+            });
+        }
+
+        history.move_tiles(from, to, &[uvec2(1, 0)]);
+
+        let objects: Vec<ObjectId> = history
+            .undo_stack
+            .iter()
+            .flatten()
+            .filter_map(|command| match command {
+                Command::SetTile { object, .. } => Some(*object),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(objects, [from, to]);
+    }
+}
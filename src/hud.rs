@@ -0,0 +1,349 @@
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use macroquad::prelude::*;
+use rapier2d::prelude::*;
+
+/// Shows information about the player's ship on top of the world, in
+/// screen space: its speed, heading, hull and resources, arrows pointing
+/// at objects which are off of the screen, a minimap of nearby tile maps,
+/// and messages from trigger zones.
+#[derive(Clone, Debug)]
+pub struct Hud {
+    pub enabled: bool,
+    /// How far from the player in world units the minimap shows.
+    pub minimap_range: f32,
+    /// The width and height of the minimap in pixels.
+    pub minimap_size: f32,
+    /// How far from the edges of the screen off-screen indicators sit.
+    pub indicator_margin: f32,
+    /// The message being shown and the time it was shown at.
+    pub message: Option<(String, f64)>,
+}
+
+impl Hud {
+    const COLOR: Color = Color::new(0.5, 1.0, 0.7, 0.9);
+    const DIM_COLOR: Color = Color::new(0.5, 1.0, 0.7, 0.3);
+    const WARNING_COLOR: Color = Color::new(1.0, 0.35, 0.25, 0.9);
+    const FONT_SIZE: f32 = 24.0;
+    const MESSAGE_FONT_SIZE: f32 = 40.0;
+    /// How long messages are shown for in seconds, the last of which they
+    /// spend fading out.
+    const MESSAGE_SECONDS: f64 = 3.0;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            minimap_range: 512.0,
+            minimap_size: 192.0,
+            indicator_margin: 32.0,
+            message: None,
+        }
+    }
+
+    /// Shows `text` across the top of the screen for a few seconds.
+    pub fn show_message(&mut self, text: &str) {
+        self.message = Some((text.to_string(), get_time()));
+    }
+
+    pub fn draw(
+        &self,
+        camera: &Camera2D,
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        player: Option<ObjectId>,
+        level_complete: bool,
+    ) {
+        let player = objects.iter().find(|object| Some(object.id) == player);
+
+        set_default_camera();
+
+        if let Some(player) = player {
+            self.draw_readouts(physics_world, player);
+            self.draw_resources(player);
+            self.draw_minimap(physics_world, objects, player);
+        }
+
+        self.draw_indicators(camera, physics_world, objects, player);
+        self.draw_message();
+
+        if level_complete {
+            draw_centered_text(
+                "LEVEL COMPLETE - R TO RESPAWN",
+                screen_height() * 0.5,
+                Self::COLOR,
+            );
+        }
+
+        set_camera(camera);
+    }
+
+    fn draw_message(&self) {
+        let Some((text, shown_at)) = &self.message else {
+            return;
+        };
+
+        let remaining = Self::MESSAGE_SECONDS - (get_time() - shown_at);
+        if remaining <= 0.0 {
+            return;
+        }
+
+        let color = Color {
+            a: Self::COLOR.a * remaining.min(1.0) as f32,
+            ..Self::COLOR
+        };
+
+        draw_centered_text(text, screen_height() * 0.2, color);
+    }
+
+    /// Flight mode, speed, heading and hull in the bottom left corner of the
+    /// screen.
+    fn draw_readouts(&self, physics_world: &PhysicsWorld, player: &Object) {
+        let rigid_body = &physics_world.rigid_body_set[player.rigid_body];
+        let velocity = Vec2::from(*rigid_body.linvel());
+        let forward = Vec2::from_angle(rigid_body.rotation().angle());
+
+        // Compass bearings start pointing up and go clockwise, and the
+        // world's y axis points down the screen
+        let heading = (forward.x.atan2(-forward.y).to_degrees() + 360.0) % 360.0;
+
+        let left = 20.0;
+        let bottom = screen_height() - 20.0;
+
+        let compass = vec2(left + 48.0, bottom - 48.0);
+        draw_circle_lines(compass.x, compass.y, 40.0, 2.0, Self::DIM_COLOR);
+
+        let nose = compass + forward * 36.0;
+        draw_line(compass.x, compass.y, nose.x, nose.y, 3.0, Self::COLOR);
+
+        if velocity.length() > 0.1 {
+            let drift = compass + velocity.clamp_length_max(36.0);
+            draw_line(compass.x, compass.y, drift.x, drift.y, 1.0, Self::COLOR);
+            draw_circle(drift.x, drift.y, 3.0, Self::COLOR);
+        }
+
+        let text_left = left + 110.0;
+        draw_text(
+            &format!("SPD {:6.1}", velocity.length()),
+            text_left,
+            bottom - 64.0,
+            Self::FONT_SIZE,
+            Self::COLOR,
+        );
+        draw_text(
+            &format!("HDG {:5.0}", heading),
+            text_left,
+            bottom - 40.0,
+            Self::FONT_SIZE,
+            Self::COLOR,
+        );
+
+        if let Some(flight_model) = player.flight_model() {
+            draw_text(
+                flight_model.mode.name(),
+                text_left,
+                bottom - 88.0,
+                Self::FONT_SIZE,
+                Self::COLOR,
+            );
+        }
+
+        let Some(tile_map) = player.tile_map() else {
+            draw_text(
+                "HULL  --",
+                text_left,
+                bottom - 16.0,
+                Self::FONT_SIZE,
+                Self::DIM_COLOR,
+            );
+            return;
+        };
+
+        let hull = tile_map.hull();
+        draw_bar("HULL", hull, hull < 0.3, vec2(text_left, bottom - 16.0));
+    }
+
+    /// Fuel, energy and heat, next to the other readouts. Heat turns to
+    /// the warning color while the ship is overheated.
+    fn draw_resources(&self, player: &Object) {
+        let Some(resources) = player.resources() else {
+            return;
+        };
+
+        let left = 20.0 + 110.0 + 200.0;
+        let bottom = screen_height() - 20.0;
+
+        let fuel = resources.fuel / resources.fuel_capacity.max(f32::EPSILON);
+        let energy = resources.energy / resources.energy_capacity.max(f32::EPSILON);
+        let heat = resources.heat / resources.heat_capacity.max(f32::EPSILON);
+
+        if resources.refuelling && fuel < 1.0 {
+            draw_text(
+                "REFUELLING",
+                left,
+                bottom - 88.0,
+                Self::FONT_SIZE,
+                Self::COLOR,
+            );
+        }
+
+        draw_bar("FUEL", fuel, fuel < 0.2, vec2(left, bottom - 64.0));
+        draw_bar("ENRG", energy, false, vec2(left, bottom - 40.0));
+        draw_bar(
+            "HEAT",
+            heat,
+            resources.overheated,
+            vec2(left, bottom - 16.0),
+        );
+    }
+
+    /// The solid tiles of nearby tile maps and the positions of other
+    /// objects, centered on the player, in the top right corner.
+    fn draw_minimap(&self, physics_world: &PhysicsWorld, objects: &[Object], player: &Object) {
+        let size = self.minimap_size;
+        let area = Rect::new(screen_width() - size - 20.0, 20.0, size, size);
+        let center = area.center();
+        let scale = size / 2.0 / self.minimap_range;
+
+        let player_position =
+            Vec2::from(*physics_world.rigid_body_set[player.rigid_body].translation());
+        let to_minimap = |point: Vec2| center + (point - player_position) * scale;
+
+        draw_rectangle(
+            area.x,
+            area.y,
+            area.w,
+            area.h,
+            Color::new(0.0, 0.0, 0.0, 0.5),
+        );
+        draw_rectangle_lines(area.x, area.y, area.w, area.h, 2.0, Self::DIM_COLOR);
+
+        for object in objects {
+            let position = physics_world.rigid_body_set[object.rigid_body].position();
+
+            let (Some(tile_map), Some(tile_size)) = (object.tile_map(), object.tile_size()) else {
+                let point = to_minimap(position.translation.vector.into());
+
+                if area.contains(point) && !object.is_projectile() {
+                    draw_circle(point.x, point.y, 2.0, Self::DIM_COLOR);
+                }
+
+                continue;
+            };
+
+            let color = if object.id == player.id {
+                Self::COLOR
+            } else {
+                Self::DIM_COLOR
+            };
+            let tile_pixels = (tile_size * scale).max(Vec2::ONE);
+
+            for index in tile_map.solid_tiles() {
+                let local = object.tile_to_local(index.as_ivec2()).unwrap() + tile_size / 2.0;
+                let point = to_minimap((position * Point::from(local)).into());
+
+                if area.contains(point) {
+                    draw_rectangle(
+                        point.x - tile_pixels.x / 2.0,
+                        point.y - tile_pixels.y / 2.0,
+                        tile_pixels.x,
+                        tile_pixels.y,
+                        color,
+                    );
+                }
+            }
+        }
+
+        draw_circle(center.x, center.y, 3.0, Self::COLOR);
+    }
+
+    /// Arrows at the edges of the screen pointing towards objects which
+    /// can't be seen, labelled with their distance from the player.
+    fn draw_indicators(
+        &self,
+        camera: &Camera2D,
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        player: Option<&Object>,
+    ) {
+        let screen = vec2(screen_width(), screen_height());
+        let screen_center = screen / 2.0;
+        let bounds = screen_center - self.indicator_margin;
+
+        let player_position = player.map(|player| {
+            Vec2::from(*physics_world.rigid_body_set[player.rigid_body].translation())
+        });
+
+        for object in objects {
+            if player.is_some_and(|player| player.id == object.id) || object.is_projectile() {
+                continue;
+            }
+
+            let position =
+                Vec2::from(*physics_world.rigid_body_set[object.rigid_body].translation());
+            let on_screen = camera.world_to_screen(position);
+
+            let offset = on_screen - screen_center;
+            if offset.x.abs() <= screen_center.x && offset.y.abs() <= screen_center.y {
+                continue;
+            }
+
+            // Scales the offset down until it touches the inset edge
+            let fit = (bounds.x / offset.x.abs()).min(bounds.y / offset.y.abs());
+            let point = screen_center + offset * fit;
+            let direction = offset.normalize();
+
+            let tip = point + direction * 10.0;
+            let side = direction.perp() * 7.0;
+            draw_triangle(tip, point - side, point + side, Self::COLOR);
+
+            if let Some(player_position) = player_position {
+                let distance = format!("{:.0}", player_position.distance(position));
+                let label = point - direction * 24.0;
+                let dimensions = measure_text(&distance, None, Self::FONT_SIZE as u16, 1.0);
+
+                draw_text(
+                    &distance,
+                    label.x - dimensions.width / 2.0,
+                    label.y + dimensions.offset_y / 2.0,
+                    Self::FONT_SIZE,
+                    Self::COLOR,
+                );
+            }
+        }
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A labelled bar `fill` of the way full, with its text's baseline at
+/// `position`.
+fn draw_bar(label: &str, fill: f32, warning: bool, position: Vec2) {
+    let color = if warning {
+        Hud::WARNING_COLOR
+    } else {
+        Hud::COLOR
+    };
+
+    draw_text(label, position.x, position.y, Hud::FONT_SIZE, color);
+
+    let bar = Rect::new(position.x + 56.0, position.y - 14.0, 120.0, 14.0);
+    draw_rectangle_lines(bar.x, bar.y, bar.w, bar.h, 2.0, color);
+    draw_rectangle(bar.x, bar.y, bar.w * fill.clamp(0.0, 1.0), bar.h, color);
+}
+
+/// Draws a message across the middle of the screen at the height `y`.
+fn draw_centered_text(text: &str, y: f32, color: Color) {
+    let dimensions = measure_text(text, None, Hud::MESSAGE_FONT_SIZE as u16, 1.0);
+
+    draw_text(
+        text,
+        (screen_width() - dimensions.width) / 2.0,
+        y,
+        Hud::MESSAGE_FONT_SIZE,
+        color,
+    );
+}
@@ -0,0 +1,480 @@
+use crate::collider_preset::ColliderPreset;
+use crate::collision_layer::CollisionLayer;
+use crate::component::Component;
+use crate::history::{Command, History};
+use crate::level::Level;
+use crate::material::PhysicsMaterial;
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use macroquad::prelude::*;
+use macroquad::ui::{hash, root_ui, Ui};
+use rapier2d::prelude::{Collider, InteractionGroups};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Lets an object be clicked on to select it, then shows its components'
+/// fields, rigid body and collider in a window where they can be tuned
+/// while playing. Tuned values can be saved to the level file.
+#[derive(Clone, Debug)]
+pub struct Inspector {
+    pub enabled: bool,
+    pub selected: Option<ObjectId>,
+    /// Components edited since the mouse was last released, with their
+    /// values from before, so that a whole drag is undone at once.
+    edits: Vec<(ObjectId, usize, Component)>,
+}
+
+/// Something which is shown each tunable field of an object in turn, and
+/// may change it. Fields are grouped into sections, and a field's name
+/// is only unique within its section.
+pub trait Inspect {
+    fn section(&mut self, name: &str);
+
+    fn number(&mut self, name: &str, value: &mut f32, range: Range<f32>);
+
+    fn flag(&mut self, name: &str, value: &mut bool);
+
+    fn integer(&mut self, name: &str, value: &mut u32, range: Range<u32>) {
+        let mut number = *value as f32;
+        self.number(name, &mut number, range.start as f32..range.end as f32);
+        *value = number.round().max(0.0) as u32;
+    }
+
+    fn vector(&mut self, name: &str, value: &mut Vec2, range: Range<f32>) {
+        self.number(&format!("{name}.x"), &mut value.x, range.clone());
+        self.number(&format!("{name}.y"), &mut value.y, range);
+    }
+
+    /// Picks one of `options` by its index.
+    fn choice(&mut self, name: &str, value: &mut usize, options: &[&str]) {
+        let last = options.len().saturating_sub(1);
+        let mut index = *value as u32;
+        self.integer(name, &mut index, 0..last as u32);
+        *value = (index as usize).min(last);
+    }
+
+    /// Whether any field has been changed since this was last called.
+    fn take_edited(&mut self) -> bool {
+        false
+    }
+
+    fn color(&mut self, name: &str, value: &mut Color) {
+        self.number(&format!("{name}.r"), &mut value.r, 0.0..1.0);
+        self.number(&format!("{name}.g"), &mut value.g, 0.0..1.0);
+        self.number(&format!("{name}.b"), &mut value.b, 0.0..1.0);
+        self.number(&format!("{name}.a"), &mut value.a, 0.0..1.0);
+    }
+}
+
+/// A tuned field as it's saved in the level file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Property {
+    /// The field's section and name, separated by a slash.
+    pub name: String,
+    pub value: PropertyValue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PropertyValue {
+    Number(f32),
+    Flag(bool),
+}
+
+impl Inspector {
+    const WINDOW_POSITION: Vec2 = vec2(20.0, 260.0);
+    const WINDOW_SIZE: Vec2 = vec2(360.0, 480.0);
+
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            selected: None,
+            edits: Vec::new(),
+        }
+    }
+
+    /// Selects the object under the mouse when clicked, unless `can_select`
+    /// is false, and shows the selected object's fields. Edits to
+    /// components are recorded in `history`.
+    pub fn update(
+        &mut self,
+        camera: &Camera2D,
+        can_select: bool,
+        history: &mut History,
+        physics_world: &mut PhysicsWorld,
+        objects: &mut [Object],
+        level_objects: &[ObjectId],
+    ) {
+        let mouse = Vec2::from(mouse_position());
+
+        if can_select
+            && is_mouse_button_pressed(MouseButton::Left)
+            && !root_ui().is_mouse_over(mouse)
+        {
+            let point = camera.screen_to_world(mouse);
+            self.selected = physics_world
+                .collider_at(point.into())
+                .and_then(|collider| {
+                    objects
+                        .iter()
+                        .find(|object| object.collider == collider)
+                        .map(|object| object.id)
+                });
+        }
+
+        let Some(index) = objects
+            .iter()
+            .position(|object| Some(object.id) == self.selected)
+        else {
+            self.selected = None;
+            self.record_edits(history, objects);
+            return;
+        };
+
+        let mut save = false;
+        let mut edited = Vec::new();
+
+        root_ui().window(hash!(), Self::WINDOW_POSITION, Self::WINDOW_SIZE, |ui| {
+            let object = &mut objects[index];
+            let rigid_body = &physics_world.rigid_body_set[object.rigid_body];
+
+            ui.label(None, &format!("Object {}", index));
+            ui.label(
+                None,
+                &format!(
+                    "position {:.1}, {:.1}  speed {:.1}",
+                    rigid_body.translation().x,
+                    rigid_body.translation().y,
+                    rigid_body.linvel().norm(),
+                ),
+            );
+
+            save = ui.button(None, "Save to level");
+
+            let id = object.id;
+            edited = inspect_object(
+                object,
+                physics_world,
+                &mut UiInspect {
+                    ui,
+                    object: id,
+                    section: String::new(),
+                    edited: false,
+                },
+            );
+        });
+
+        let id = objects[index].id;
+        for (component, old) in edited {
+            let editing = self
+                .edits
+                .iter()
+                .any(|edit| edit.0 == id && edit.1 == component);
+
+            if !editing {
+                self.edits.push((id, component, old));
+            }
+        }
+
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.record_edits(history, objects);
+        }
+
+        if save {
+            match Level::from_objects(physics_world, objects, level_objects).save(Level::PATH) {
+                Ok(()) => info!("Saved level to {}", Level::PATH),
+                Err(error) => error!("Failed to save level: {}", error),
+            }
+        }
+    }
+
+    fn record_edits(&mut self, history: &mut History, objects: &[Object]) {
+        if self.edits.is_empty() {
+            return;
+        }
+
+        history.begin_group();
+
+        for (id, component, old) in self.edits.drain(..) {
+            let Some(new) = objects
+                .iter()
+                .find(|object| object.id == id)
+                .and_then(|object| object.components.get(component))
+            else {
+                continue;
+            };
+
+            history.push(Command::EditComponent {
+                object: id,
+                component,
+                old,
+                new: new.clone(),
+            });
+        }
+
+        history.end_group();
+    }
+
+    /// Outlines the selected object's collider.
+    pub fn draw(&self, camera: &Camera2D, physics_world: &PhysicsWorld, objects: &[Object]) {
+        let Some(object) = objects
+            .iter()
+            .find(|object| Some(object.id) == self.selected)
+        else {
+            return;
+        };
+
+        let collider = &physics_world.collider_set[object.collider];
+        let pixel = crate::debug::pixel_size(camera);
+
+        crate::debug::draw_shape(collider.shape(), collider.position(), pixel * 2.0, YELLOW);
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shows every tunable field of an object to `inspect`: its rigid body,
+/// its collider's material and each of its components. Returns the index
+/// and previous value of each component which `inspect` edited.
+pub fn inspect_object(
+    object: &mut Object,
+    physics_world: &mut PhysicsWorld,
+    inspect: &mut dyn Inspect,
+) -> Vec<(usize, Component)> {
+    let rigid_body = &mut physics_world.rigid_body_set[object.rigid_body];
+    inspect.section("rigid body");
+
+    let mut linear_damping = rigid_body.linear_damping();
+    inspect.number("linear damping", &mut linear_damping, 0.0..5.0);
+    if linear_damping != rigid_body.linear_damping() {
+        rigid_body.set_linear_damping(linear_damping);
+    }
+
+    let mut angular_damping = rigid_body.angular_damping();
+    inspect.number("angular damping", &mut angular_damping, 0.0..5.0);
+    if angular_damping != rigid_body.angular_damping() {
+        rigid_body.set_angular_damping(angular_damping);
+    }
+
+    let mut ccd = rigid_body.is_ccd_enabled();
+    inspect.flag("ccd", &mut ccd);
+    if ccd != rigid_body.is_ccd_enabled() {
+        rigid_body.enable_ccd(ccd);
+    }
+
+    let collider = &mut physics_world.collider_set[object.collider];
+    inspect.section("collider");
+
+    // The rigid body's mass isn't updated until the next step, so the
+    // collider's is shown instead
+    let mut mass = collider.mass();
+    inspect.number("mass", &mut mass, 0.1..1000.0);
+    if mass != collider.mass() && mass > 0.0 {
+        let mut mass_properties = collider.mass_properties();
+        mass_properties.set_mass(mass, true);
+        collider.set_mass_properties(mass_properties);
+
+        // Tile maps would otherwise lose it when their collider is rebuilt
+        if let Some(tile_map) = object.tile_map_mut() {
+            tile_map.tuned_mass = Some(mass);
+        }
+    }
+
+    let mut friction = collider.friction();
+    inspect.number("friction", &mut friction, 0.0..2.0);
+    if friction != collider.friction() {
+        collider.set_friction(friction);
+    }
+
+    inspect_preset::<CollisionLayer>(inspect, "layer", collider, "all", |collider| {
+        collider.set_collision_groups(InteractionGroups::all())
+    });
+
+    let mut restitution = collider.restitution();
+    inspect.number("restitution", &mut restitution, 0.0..1.0);
+    if restitution != collider.restitution() {
+        collider.set_restitution(restitution);
+    }
+
+    // Tile maps use the materials of their tiles instead
+    inspect_preset::<PhysicsMaterial>(inspect, "material", collider, "custom", |_| {});
+
+    let mut edited = Vec::new();
+    inspect.take_edited();
+
+    for (index, component) in object.components.iter_mut().enumerate() {
+        let old = component.clone();
+
+        inspect.section(&format!("{} {}", index, component.name()));
+        component.inspect(inspect);
+
+        if inspect.take_edited() {
+            edited.push((index, old));
+        }
+    }
+
+    edited
+}
+
+/// Picks which of `T`'s presets a collider has, with `other` for a
+/// collider which doesn't match any of them. Picking `other` calls
+/// `set_other`.
+fn inspect_preset<T: ColliderPreset>(
+    inspect: &mut dyn Inspect,
+    name: &str,
+    collider: &mut Collider,
+    other: &str,
+    set_other: impl FnOnce(&mut Collider),
+) {
+    let mut options: Vec<&str> = T::PRESETS.iter().map(|preset| preset.name()).collect();
+    options.push(other);
+
+    let current = T::of(collider);
+    let mut index = T::PRESETS
+        .iter()
+        .position(|&preset| Some(preset) == current)
+        .unwrap_or(T::PRESETS.len());
+    inspect.choice(name, &mut index, &options);
+
+    let picked = T::PRESETS.get(index).copied();
+    if picked == current {
+        return;
+    }
+
+    match picked {
+        Some(preset) => preset.set(collider),
+        None => set_other(collider),
+    }
+}
+
+/// Shows fields as sliders and checkboxes in a window. Widgets are kept
+/// apart for each object, so that selecting another object doesn't carry
+/// over what was being typed.
+struct UiInspect<'a> {
+    ui: &'a mut Ui,
+    object: ObjectId,
+    section: String,
+    edited: bool,
+}
+
+impl Inspect for UiInspect<'_> {
+    fn section(&mut self, name: &str) {
+        self.ui.separator();
+        self.ui.label(None, name);
+        self.section = name.to_string();
+    }
+
+    fn number(&mut self, name: &str, value: &mut f32, range: Range<f32>) {
+        let mut edited = *value;
+        self.ui.slider(
+            hash!((self.object, &self.section, name)),
+            name,
+            range.clone(),
+            &mut edited,
+        );
+
+        // The slider writes back its text every frame, rounded to 2
+        // decimals and clamped to the range, even when it isn't touched.
+        // Only a value other than that was dragged or typed.
+        let shown = format!("{:.2}", *value).parse().unwrap_or(*value);
+        if edited != shown && edited != shown.clamp(range.start, range.end) {
+            *value = edited;
+            self.edited = true;
+        }
+    }
+
+    fn flag(&mut self, name: &str, value: &mut bool) {
+        let old = *value;
+        self.ui
+            .checkbox(hash!((self.object, &self.section, name)), name, value);
+        self.edited |= *value != old;
+    }
+
+    fn choice(&mut self, name: &str, value: &mut usize, options: &[&str]) {
+        let old = *value;
+        self.ui.combo_box(
+            hash!((self.object, &self.section, name)),
+            name,
+            options,
+            &mut *value,
+        );
+        self.edited |= *value != old;
+    }
+
+    fn take_edited(&mut self) -> bool {
+        std::mem::take(&mut self.edited)
+    }
+}
+
+/// Reads every field into a list of properties.
+#[derive(Default)]
+pub struct CollectProperties {
+    section: String,
+    pub properties: Vec<Property>,
+}
+
+impl Inspect for CollectProperties {
+    fn section(&mut self, name: &str) {
+        self.section = name.to_string();
+    }
+
+    fn number(&mut self, name: &str, value: &mut f32, _range: Range<f32>) {
+        self.properties.push(Property {
+            name: format!("{}/{}", self.section, name),
+            value: PropertyValue::Number(*value),
+        });
+    }
+
+    fn flag(&mut self, name: &str, value: &mut bool) {
+        self.properties.push(Property {
+            name: format!("{}/{}", self.section, name),
+            value: PropertyValue::Flag(*value),
+        });
+    }
+}
+
+/// Sets each field which has a matching property.
+pub struct ApplyProperties<'a> {
+    section: String,
+    properties: HashMap<&'a str, PropertyValue>,
+}
+
+impl<'a> ApplyProperties<'a> {
+    pub fn new(properties: &'a [Property]) -> Self {
+        Self {
+            section: String::new(),
+            properties: properties
+                .iter()
+                .map(|property| (property.name.as_str(), property.value))
+                .collect(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<PropertyValue> {
+        self.properties
+            .get(format!("{}/{}", self.section, name).as_str())
+            .copied()
+    }
+}
+
+impl Inspect for ApplyProperties<'_> {
+    fn section(&mut self, name: &str) {
+        self.section = name.to_string();
+    }
+
+    fn number(&mut self, name: &str, value: &mut f32, _range: Range<f32>) {
+        if let Some(PropertyValue::Number(number)) = self.get(name) {
+            *value = number;
+        }
+    }
+
+    fn flag(&mut self, name: &str, value: &mut bool) {
+        if let Some(PropertyValue::Flag(flag)) = self.get(name) {
+            *value = flag;
+        }
+    }
+}
@@ -13,7 +13,7 @@ impl Keybinds {
 
             entry.1 = entry
                 .1
-                .update(key_codes.into_iter().any(|key_code| is_key_down(*key_code)));
+                .update(key_codes.iter().any(|key_code| is_key_down(*key_code)));
         }
     }
 
@@ -59,8 +59,62 @@ impl Keybinds {
 
         map.insert(KeyAction::Boost, (vec![KeyCode::W, KeyCode::Up], Off));
         map.insert(KeyAction::Slow, (vec![KeyCode::S, KeyCode::Down], Off));
+        map.insert(
+            KeyAction::StrafeLeft,
+            (vec![KeyCode::A, KeyCode::Left], Off),
+        );
+        map.insert(
+            KeyAction::StrafeRight,
+            (vec![KeyCode::D, KeyCode::Right], Off),
+        );
+        map.insert(KeyAction::FlightMode, (vec![KeyCode::V], Off));
+        map.insert(KeyAction::Fire, (vec![KeyCode::Space], Off));
+        map.insert(KeyAction::Undock, (vec![KeyCode::F], Off));
+        map.insert(KeyAction::Tow, (vec![KeyCode::T], Off));
+        map.insert(KeyAction::Respawn, (vec![KeyCode::R], Off));
+        map.insert(KeyAction::SwitchShip, (vec![KeyCode::Q], Off));
+        map.insert(
+            KeyAction::ZoomIn,
+            (vec![KeyCode::KpAdd, KeyCode::PageUp], Off),
+        );
+        map.insert(
+            KeyAction::ZoomOut,
+            (vec![KeyCode::KpSubtract, KeyCode::PageDown], Off),
+        );
+        map.insert(KeyAction::TacticalView, (vec![KeyCode::M], Off));
+        map.insert(KeyAction::PixelPerfect, (vec![KeyCode::F4], Off));
+        map.insert(KeyAction::PostProcessing, (vec![KeyCode::F6], Off));
+        map.insert(KeyAction::Bloom, (vec![KeyCode::F7], Off));
+        map.insert(KeyAction::Crt, (vec![KeyCode::F8], Off));
+        map.insert(KeyAction::ChromaticAberration, (vec![KeyCode::F9], Off));
+        map.insert(KeyAction::DamageVignette, (vec![KeyCode::F10], Off));
+        map.insert(KeyAction::Hud, (vec![KeyCode::H], Off));
+        map.insert(KeyAction::Trajectory, (vec![KeyCode::P], Off));
         map.insert(KeyAction::Pause, (vec![KeyCode::Escape], Off));
         map.insert(KeyAction::Debug, (vec![KeyCode::F3], Off));
+        map.insert(KeyAction::Editor, (vec![KeyCode::F2], Off));
+        map.insert(KeyAction::Inspector, (vec![KeyCode::I], Off));
+        map.insert(KeyAction::EditorNextTile, (vec![KeyCode::Tab], Off));
+        map.insert(
+            KeyAction::EditorBrushLarger,
+            (vec![KeyCode::RightBracket, KeyCode::Equal], Off),
+        );
+        map.insert(
+            KeyAction::EditorBrushSmaller,
+            (vec![KeyCode::LeftBracket, KeyCode::Minus], Off),
+        );
+        map.insert(
+            KeyAction::EditorRectangle,
+            (vec![KeyCode::LeftShift, KeyCode::RightShift], Off),
+        );
+        map.insert(
+            KeyAction::EditorDelete,
+            (vec![KeyCode::Delete, KeyCode::Backspace], Off),
+        );
+        map.insert(KeyAction::EditorDuplicate, (vec![KeyCode::C], Off));
+        map.insert(KeyAction::Undo, (vec![KeyCode::Z], Off));
+        map.insert(KeyAction::Redo, (vec![KeyCode::Y], Off));
+        map.insert(KeyAction::Save, (vec![KeyCode::F5], Off));
 
         map
     }
@@ -78,8 +132,38 @@ impl Default for Keybinds {
 pub enum KeyAction {
     Boost,
     Slow,
+    StrafeLeft,
+    StrafeRight,
+    FlightMode,
+    Fire,
+    Undock,
+    Tow,
+    Respawn,
+    SwitchShip,
+    ZoomIn,
+    ZoomOut,
+    TacticalView,
+    PixelPerfect,
+    PostProcessing,
+    Bloom,
+    Crt,
+    ChromaticAberration,
+    DamageVignette,
+    Hud,
+    Trajectory,
     Pause,
     Debug,
+    Editor,
+    Inspector,
+    EditorNextTile,
+    EditorBrushLarger,
+    EditorBrushSmaller,
+    EditorRectangle,
+    EditorDelete,
+    EditorDuplicate,
+    Undo,
+    Redo,
+    Save,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
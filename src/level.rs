@@ -0,0 +1,140 @@
+use crate::inspector::{inspect_object, ApplyProperties, CollectProperties, Property};
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use crate::tilemap::Tile;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+/// The saved state of the world that isn't created by `main`. Objects
+/// are referred to by their index in `App::level_objects`, so objects
+/// spawned while playing, like projectiles and fragments, aren't saved.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Level {
+    pub tile_maps: Vec<LevelTileMap>,
+    pub objects: Vec<LevelObject>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelTileMap {
+    pub object: usize,
+    pub contents: Vec<Vec<Tile>>,
+}
+
+/// The fields of an object tuned with the `Inspector`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelObject {
+    pub object: usize,
+    pub properties: Vec<Property>,
+}
+
+impl Level {
+    pub const PATH: &'static str = "level.bin";
+
+    pub fn from_objects(
+        physics_world: &mut PhysicsWorld,
+        objects: &mut [Object],
+        level_objects: &[ObjectId],
+    ) -> Self {
+        let mut level = Self::default();
+
+        for index in 0..level_objects.len() {
+            let Some(object) = level_object(objects, level_objects, index) else {
+                continue;
+            };
+
+            if let Some(tile_map) = object.tile_map() {
+                level.tile_maps.push(LevelTileMap {
+                    object: index,
+                    contents: tile_map.contents.clone(),
+                });
+            }
+
+            let mut collect = CollectProperties::default();
+            inspect_object(object, physics_world, &mut collect);
+
+            level.objects.push(LevelObject {
+                object: index,
+                properties: collect.properties,
+            });
+        }
+
+        level
+    }
+
+    /// Copies the level's tiles and tuned fields onto the matching
+    /// objects. Tile maps which don't match an object of the same size are
+    /// skipped.
+    pub fn apply(
+        &self,
+        physics_world: &mut PhysicsWorld,
+        objects: &mut [Object],
+        level_objects: &[ObjectId],
+    ) {
+        for level_object in &self.objects {
+            let Some(object) = self::level_object(objects, level_objects, level_object.object)
+            else {
+                warn!(
+                    "Level properties for object {} have no matching object",
+                    level_object.object
+                );
+                continue;
+            };
+
+            let mut apply = ApplyProperties::new(&level_object.properties);
+            inspect_object(object, physics_world, &mut apply);
+        }
+
+        for level_tile_map in &self.tile_maps {
+            let Some(tile_map) = level_object(objects, level_objects, level_tile_map.object)
+                .and_then(Object::tile_map_mut)
+            else {
+                warn!(
+                    "Level tile map for object {} has no matching object",
+                    level_tile_map.object
+                );
+                continue;
+            };
+
+            if tile_map.contents.len() != level_tile_map.contents.len()
+                || tile_map.contents[0].len() != level_tile_map.contents[0].len()
+            {
+                warn!(
+                    "Level tile map for object {} has the wrong size",
+                    level_tile_map.object
+                );
+                continue;
+            }
+
+            for (x, column) in level_tile_map.contents.iter().enumerate() {
+                for (y, &tile) in column.iter().enumerate() {
+                    tile_map.set(uvec2(x as u32, y as u32), tile);
+                }
+            }
+
+            tile_map.reset_hull();
+        }
+    }
+
+    pub fn save(&self, path: &str) -> bincode::Result<()> {
+        bincode::serialize_into(BufWriter::new(File::create(path)?), self)
+    }
+
+    pub fn load(path: &str) -> bincode::Result<Self> {
+        bincode::deserialize_from(BufReader::new(File::open(path)?))
+    }
+}
+
+/// The object at `index` in `level_objects`, if it hasn't been removed.
+fn level_object<'a>(
+    objects: &'a mut [Object],
+    level_objects: &[ObjectId],
+    index: usize,
+) -> Option<&'a mut Object> {
+    let id = *level_objects.get(index)?;
+
+    objects.iter_mut().find(|object| object.id == id)
+}
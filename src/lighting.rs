@@ -0,0 +1,321 @@
+use crate::graphics::{draw_full, TargetCamera};
+use crate::object::Object;
+use crate::physics_world::PhysicsWorld;
+use macroquad::prelude::*;
+
+/// Lights the world with point lights. Each frame the lights are drawn
+/// into a light map matching the screen, with shadows cast by the
+/// colliders of tile maps. The tri-pixel material then samples the light
+/// map once per tri-pixel, so lighting never splits a triangle.
+pub struct Lighting {
+    pub enabled: bool,
+    /// The light everything receives, even in shadow.
+    pub ambient: Color,
+    /// Lights to draw this frame, which are cleared once drawn.
+    pub lights: Vec<Light>,
+    flashes: Vec<Flash>,
+    light_map: RenderTarget,
+    /// Each light which casts shadows is drawn here before being added
+    /// to the light map, so its shadows don't darken other lights.
+    scratch: RenderTarget,
+    falloff: Texture2D,
+    additive_material: Material,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub position: Vec2,
+    pub color: Color,
+    pub radius: f32,
+}
+
+/// A light which fades out over a short time, such as a muzzle flash.
+#[derive(Clone, Copy, Debug)]
+struct Flash {
+    light: Light,
+    duration: f32,
+    remaining: f32,
+}
+
+impl Lighting {
+    const FALLOFF_SIZE: u16 = 64;
+
+    pub fn new() -> Self {
+        let (light_map, scratch) = Self::make_targets();
+
+        Self {
+            enabled: true,
+            ambient: Color::new(0.45, 0.45, 0.55, 1.0),
+            lights: Vec::new(),
+            flashes: Vec::new(),
+            light_map,
+            scratch,
+            falloff: make_falloff_texture(Self::FALLOFF_SIZE),
+            additive_material: make_additive_material(),
+        }
+    }
+
+    pub fn light_map(&self) -> &Texture2D {
+        &self.light_map.texture
+    }
+
+    /// Adds a light which fades out over `duration` seconds.
+    pub fn flash(&mut self, light: Light, duration: f32) {
+        self.flashes.push(Flash {
+            light,
+            duration,
+            remaining: duration,
+        });
+    }
+
+    /// Draws the light map for `camera`. The camera to draw the world
+    /// with must be set again afterwards.
+    pub fn render(
+        &mut self,
+        camera: &Camera2D,
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        interpolation: f32,
+        delta_time: f32,
+    ) {
+        if self.light_map.texture.size() != vec2(screen_width(), screen_height()) {
+            (self.light_map, self.scratch) = Self::make_targets();
+        }
+
+        for flash in &mut self.flashes {
+            flash.remaining -= delta_time;
+
+            let fade = (flash.remaining / flash.duration).max(0.0);
+            self.lights.push(Light {
+                color: Color {
+                    a: flash.light.color.a * fade,
+                    ..flash.light.color
+                },
+                ..flash.light
+            });
+        }
+
+        self.flashes.retain(|flash| flash.remaining > 0.0);
+
+        let light_map_camera = TargetCamera::new(camera, Some(self.light_map.clone()));
+        let scratch_camera = TargetCamera::new(camera, Some(self.scratch.clone()));
+
+        set_camera(&light_map_camera);
+
+        if !self.enabled {
+            clear_background(WHITE);
+            self.lights.clear();
+            return;
+        }
+
+        clear_background(self.ambient);
+
+        let occluders = occluders(physics_world, objects, interpolation);
+
+        for light in self.lights.drain(..) {
+            let nearby: Vec<_> = occluders
+                .iter()
+                .filter(|occluder| occluder.is_near(light.position, light.radius))
+                .collect();
+
+            if nearby.is_empty() {
+                set_camera(&light_map_camera);
+                gl_use_material(&self.additive_material);
+                draw_falloff(&self.falloff, &light);
+                gl_use_default_material();
+                continue;
+            }
+
+            set_camera(&scratch_camera);
+            clear_background(BLACK);
+            draw_falloff(&self.falloff, &light);
+
+            for occluder in nearby {
+                occluder.draw_shadow(&light);
+            }
+
+            // Adds the whole scratch target onto the light map
+            set_camera(&TargetCamera::full(Some(self.light_map.clone())));
+            gl_use_material(&self.additive_material);
+            draw_full(&self.scratch.texture);
+            gl_use_default_material();
+        }
+    }
+
+    fn make_targets() -> (RenderTarget, RenderTarget) {
+        let make = || {
+            let target = render_target(screen_width() as u32, screen_height() as u32);
+            target.texture.set_filter(FilterMode::Linear);
+            target
+        };
+
+        (make(), make())
+    }
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A convex polygon in world space which blocks light.
+struct Occluder {
+    points: Vec<Vec2>,
+    center: Vec2,
+    radius: f32,
+}
+
+impl Occluder {
+    fn new(points: Vec<Vec2>) -> Self {
+        let center = points.iter().sum::<Vec2>() / points.len() as f32;
+        let radius = points
+            .iter()
+            .map(|point| point.distance(center))
+            .fold(0.0, f32::max);
+
+        Self {
+            points,
+            center,
+            radius,
+        }
+    }
+
+    fn is_near(&self, position: Vec2, radius: f32) -> bool {
+        self.center.distance(position) < self.radius + radius
+    }
+
+    /// Extends each edge facing away from the light out past the light's
+    /// radius. Occluders containing the light cast no shadow, so lights
+    /// inside of tiles still light their surroundings.
+    fn draw_shadow(&self, light: &Light) {
+        let mut inside = true;
+        let mut back_edges = Vec::new();
+
+        for i in 0..self.points.len() {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % self.points.len()];
+
+            let mut normal = (b - a).perp();
+            if normal.dot((a + b) / 2.0 - self.center) < 0.0 {
+                normal = -normal;
+            }
+
+            if normal.dot(a - light.position) > 0.0 {
+                back_edges.push((a, b));
+            } else {
+                inside = false;
+            }
+        }
+
+        if inside {
+            return;
+        }
+
+        let extend =
+            |point: Vec2| point + (point - light.position).normalize_or_zero() * light.radius * 2.0;
+
+        for (a, b) in back_edges {
+            draw_triangle(a, b, extend(b), BLACK);
+            draw_triangle(a, extend(b), extend(a), BLACK);
+        }
+    }
+}
+
+/// The convex parts of the colliders of every object with a tile map.
+fn occluders(
+    physics_world: &PhysicsWorld,
+    objects: &[Object],
+    interpolation: f32,
+) -> Vec<Occluder> {
+    let mut occluders = Vec::new();
+
+    for object in objects {
+        if object.tile_map().is_none() {
+            continue;
+        }
+
+        let collider = &physics_world.collider_set[object.collider];
+        if !collider.is_enabled() {
+            continue;
+        }
+
+        let Some(compound) = collider.shape().as_compound() else {
+            continue;
+        };
+
+        let position = object.interpolated_position(physics_world, interpolation);
+
+        for (shape_position, shape) in compound.shapes() {
+            if let Some(polygon) = shape.as_convex_polygon() {
+                let transform = position * shape_position;
+                let points = polygon
+                    .points()
+                    .iter()
+                    .map(|point| Vec2::from(transform * point))
+                    .collect();
+
+                occluders.push(Occluder::new(points));
+            }
+        }
+    }
+
+    occluders
+}
+
+/// Lights are added together, so the colour's alpha scales its
+/// brightness instead of blending with what's beneath it.
+fn draw_falloff(falloff: &Texture2D, light: &Light) {
+    let Color { r, g, b, a } = light.color;
+
+    draw_texture_ex(
+        falloff,
+        light.position.x - light.radius,
+        light.position.y - light.radius,
+        Color::new(r * a, g * a, b * a, 1.0),
+        DrawTextureParams {
+            dest_size: Some(Vec2::splat(light.radius * 2.0)),
+            ..Default::default()
+        },
+    );
+}
+
+/// A white circle which fades to black towards its edge.
+fn make_falloff_texture(size: u16) -> Texture2D {
+    let mut image = Image::gen_image_color(size, size, BLACK);
+    let center = Vec2::splat(size as f32 / 2.0);
+
+    for x in 0..size as u32 {
+        for y in 0..size as u32 {
+            let distance = (vec2(x as f32, y as f32) + 0.5).distance(center) / center.x;
+            let brightness = (1.0 - distance).max(0.0).powi(2);
+
+            image.set_pixel(x, y, Color::new(brightness, brightness, brightness, 1.0));
+        }
+    }
+
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Linear);
+    texture
+}
+
+fn make_additive_material() -> Material {
+    load_material(
+        ShaderSource::Glsl {
+            vertex: include_str!("shaders/simple_vertex.glsl"),
+            fragment: include_str!("shaders/light_fragment.glsl"),
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(miniquad::BlendState::new(
+                    miniquad::Equation::Add,
+                    miniquad::BlendFactor::One,
+                    miniquad::BlendFactor::One,
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .expect("Shader code should be valid")
+}
@@ -1,16 +1,45 @@
+use crate::collision_layer::CollisionLayer;
 use crate::component::Component;
+use crate::flight_model::FlightModel;
+use crate::force_field::{FieldKind, ForceField};
+use crate::level::Level;
+use crate::material::PhysicsMaterial;
 use crate::object::Object;
+use crate::resources::Resources;
+use crate::sprite_shape::make_sprite_shape;
 use crate::tilemap::{Tile, TileMap, TileType};
+use crate::trigger::{trigger_collider, TriggerAction};
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
 
 pub mod app;
+pub mod camera;
+pub mod collider_preset;
+pub mod collision_layer;
 pub mod component;
+pub mod debug;
+pub mod editor;
+pub mod flight_model;
+pub mod force_field;
 pub mod graphics;
+pub mod history;
+pub mod hud;
+pub mod inspector;
 pub mod keybinds;
+pub mod level;
+pub mod lighting;
+pub mod material;
 pub mod object;
+pub mod particles;
 pub mod physics_world;
+pub mod post_processing;
+pub mod resources;
+pub mod settings;
+pub mod sprite_shape;
+pub mod starfield;
 pub mod tilemap;
+pub mod trajectory;
+pub mod trigger;
 
 fn window_conf() -> Conf {
     Conf {
@@ -26,55 +55,121 @@ async fn main() {
 
     const TILE_MAP_SIZE: u32 = 16;
 
+    let mut tile_map = TileMap::new(uvec2(TILE_MAP_SIZE, TILE_MAP_SIZE)).await;
+
+    for x in 0..=4 {
+        for y in 0..=4 {
+            let tile_type = match (x, y) {
+                (4, 1..=3) => TileType::Refuel,
+                (1..=3, 0) => TileType::Bumper,
+                (0, 1..=3) => TileType::Ice,
+                _ => TileType::Wall,
+            };
+
+            tile_map.set(uvec2(x, y), Tile::new(tile_type));
+        }
+    }
+
+    tile_map.reset_hull();
+
+    let tile_map_shape = tile_map
+        .make_shape(Vec2::splat(Tile::SIZE_TEXELS as f32), Vec2::ZERO)
+        .unwrap();
+
     app.objects.push(Object::new(
         &mut app.physics_world,
         RigidBodyBuilder::fixed()
             .translation(vector![-64.0, -64.0])
             .build(),
-        ColliderBuilder::new(make_shape()).build(),
+        ColliderBuilder::new(tile_map_shape)
+            .collision_groups(CollisionLayer::Station.groups())
+            .build(),
         Texture2D::from_image(&Image::gen_image_color(
             (TILE_MAP_SIZE * Tile::SIZE_PIXELS) as u16,
             (TILE_MAP_SIZE * Tile::SIZE_PIXELS) as u16,
             BLANK,
         )),
-        vec![Component::TileMap(
-            TileMap::new(uvec2(TILE_MAP_SIZE, TILE_MAP_SIZE)).await,
-        )],
+        vec![
+            Component::TileMap(tile_map),
+            Component::Light {
+                offset: vec2(52.0, 20.0),
+                color: Color::new(1.0, 0.9, 0.7, 1.0),
+                radius: 72.0,
+            },
+            Component::Light {
+                offset: vec2(20.0, 52.0),
+                color: Color::new(0.6, 0.8, 1.0, 1.0),
+                radius: 72.0,
+            },
+            Component::DockingPort {
+                offset: vec2(20.0, 40.0),
+                angle: std::f32::consts::FRAC_PI_2,
+                joint: None,
+                armed: true,
+            },
+        ],
         vec2(0.0, 0.0),
     ));
 
-    if let Component::TileMap(tile_map) = &mut app.objects[0].components[0] {
-        for x in 0..=4 {
-            for y in 0..=4 {
-                tile_map.set(
-                    uvec2(x, y),
-                    Tile {
-                        tile_type: TileType::Wall,
-                    },
-                );
-            }
-        }
-    }
+    let ship_image = load_image("assets/ship_active.png").await.unwrap();
+    let ship_shape = make_sprite_shape(
+        &ship_image,
+        vec2(ship_image.width as f32, ship_image.height as f32) / 2.0,
+        vec2(0.5, 0.5),
+    )
+    .unwrap();
 
-    app.objects.push(Object::new(
+    let mut ship = Object::new(
         &mut app.physics_world,
         RigidBodyBuilder::dynamic()
             .ccd_enabled(true)
             .can_sleep(false)
             .build(),
-        ColliderBuilder::new(make_shape()).build(),
-        load_texture("assets/ship_active.png").await.unwrap(),
+        PhysicsMaterial::Metal
+            .apply(ColliderBuilder::new(ship_shape.clone()))
+            .collision_groups(CollisionLayer::Ship.groups())
+            .build(),
+        Texture2D::from_image(&ship_image),
         vec![
             Component::FaceMouse,
             Component::Motion {
-                power: 100.0,
-                brake: 0.975,
+                flight_model: FlightModel::FLIGHT_ASSIST,
                 emitter: vec2(-8.0, 0.0),
             },
+            Component::Gun {
+                cooldown: 0.2,
+                timer: 0.0,
+                speed: 150.0,
+                damage: 25,
+                muzzle: vec2(9.0, 0.0),
+                energy_cost: 5.0,
+                heat_cost: 6.0,
+            },
+            Component::Resources(Resources::SHIP),
+            Component::DockingPort {
+                offset: vec2(8.0, 0.0),
+                angle: 0.0,
+                joint: None,
+                armed: true,
+            },
+            Component::TowCable {
+                anchor: vec2(-8.0, 0.0),
+                length: 24.0,
+                joint: None,
+            },
             Component::CameraFollow,
         ],
         vec2(0.5, 0.5),
-    ));
+    );
+
+    ship.emissive = Some(
+        load_texture("assets/ship_active_emissive.png")
+            .await
+            .unwrap(),
+    );
+
+    app.player = Some(ship.id);
+    app.objects.push(ship);
 
     app.objects.push(Object::new(
         &mut app.physics_world,
@@ -83,69 +178,253 @@ async fn main() {
             .can_sleep(false)
             .translation(vector![40.0, 0.0])
             .build(),
-        ColliderBuilder::new(make_shape()).build(),
+        PhysicsMaterial::Metal
+            .apply(ColliderBuilder::new(ship_shape))
+            .collision_groups(CollisionLayer::Ship.groups())
+            .build(),
         load_texture("assets/ship_inactive.png").await.unwrap(),
         Vec::new(),
         vec2(0.5, 0.5),
     ));
 
-    loop {
-        app.check_fixed_tick();
+    const TILE_SHIP_SIZE: u32 = 3;
 
-        app.frame_tick();
+    let mut tile_ship = TileMap::new(uvec2(TILE_SHIP_SIZE, TILE_SHIP_SIZE)).await;
 
-        next_frame().await;
+    for x in 0..TILE_SHIP_SIZE {
+        for y in 0..TILE_SHIP_SIZE {
+            let tile_type = match (x, y) {
+                (0, 0) | (0, 2) => TileType::Thruster,
+                _ => TileType::Wall,
+            };
+
+            tile_ship.set(uvec2(x, y), Tile::new(tile_type));
+        }
     }
-}
 
-// temp
-fn make_shape() -> SharedShape {
-    SharedShape::compound(vec![
-        (
-            Isometry::<Real>::identity(),
-            SharedShape::convex_polyline(vec![
-                point![-3.0, 2.0],
-                point![-4.0, 3.0],
-                point![-8.0, 3.0],
-                point![-8.0, -3.0],
-                point![-4.0, -3.0],
-                point![-3.0, -2.0],
-            ])
+    tile_ship.reset_hull();
+
+    let tile_ship_size = Vec2::splat((TILE_SHIP_SIZE * Tile::SIZE_TEXELS) as f32);
+    let tile_ship_collider = ColliderBuilder::new(
+        tile_ship
+            .make_shape(Vec2::splat(Tile::SIZE_TEXELS as f32), -tile_ship_size / 2.0)
             .unwrap(),
+    )
+    .mass_properties(
+        tile_ship.mass_properties(Vec2::splat(Tile::SIZE_TEXELS as f32), -tile_ship_size / 2.0),
+    )
+    .collision_groups(CollisionLayer::Ship.groups())
+    .build();
+
+    app.objects.push(Object::new(
+        &mut app.physics_world,
+        RigidBodyBuilder::dynamic()
+            .ccd_enabled(true)
+            .can_sleep(false)
+            .translation(vector![0.0, 40.0])
+            .build(),
+        tile_ship_collider,
+        Texture2D::from_image(&Image::gen_image_color(
+            (TILE_SHIP_SIZE * Tile::SIZE_PIXELS) as u16,
+            (TILE_SHIP_SIZE * Tile::SIZE_PIXELS) as u16,
+            BLANK,
+        )),
+        vec![
+            Component::TileMap(tile_ship),
+            Component::FaceMouse,
+            Component::Motion {
+                flight_model: FlightModel {
+                    max_thrust: 9000.0,
+                    ..FlightModel::NEWTONIAN
+                },
+                emitter: vec2(-12.0, 0.0),
+            },
+            Component::Resources(Resources::SHIP),
+            Component::CameraFollow,
+        ],
+        vec2(0.5, 0.5),
+    ));
+
+    // A planet to orbit, a nebula to slow down in and a current to ride
+    app.objects.push(Object::new(
+        &mut app.physics_world,
+        RigidBodyBuilder::fixed()
+            .translation(vector![480.0, -320.0])
+            .build(),
+        ColliderBuilder::ball(24.0)
+            .collision_groups(CollisionLayer::Station.groups())
+            .build(),
+        Texture2D::from_image(&make_planet_image(96, Color::from_hex(0x5a8fc0))),
+        vec![Component::ForceField(ForceField {
+            kind: FieldKind::Gravity { strength: 100000.0 },
+            radius: 400.0,
+        })],
+        vec2(0.5, 0.5),
+    ));
+
+    let field_texture = Texture2D::from_image(&Image::gen_image_color(2, 2, BLANK));
+
+    app.objects.push(Object::new(
+        &mut app.physics_world,
+        RigidBodyBuilder::fixed()
+            .translation(vector![-260.0, 120.0])
+            .build(),
+        ColliderBuilder::ball(80.0)
+            .sensor(true)
+            .collision_groups(CollisionLayer::Sensor.groups())
+            .build(),
+        field_texture.clone(),
+        vec![
+            Component::ForceField(ForceField {
+                kind: FieldKind::Nebula { half_life: 1.5 },
+                radius: 80.0,
+            }),
+            Component::Light {
+                offset: Vec2::ZERO,
+                color: Color::new(0.7, 0.4, 1.0, 1.0),
+                radius: 120.0,
+            },
+        ],
+        vec2(0.5, 0.5),
+    ));
+
+    app.objects.push(Object::new(
+        &mut app.physics_world,
+        RigidBodyBuilder::fixed()
+            .translation(vector![0.0, 260.0])
+            .build(),
+        ColliderBuilder::ball(100.0)
+            .sensor(true)
+            .collision_groups(CollisionLayer::Sensor.groups())
+            .build(),
+        field_texture.clone(),
+        vec![Component::ForceField(ForceField {
+            kind: FieldKind::Current {
+                acceleration: vec2(30.0, 0.0),
+            },
+            radius: 100.0,
+        })],
+        vec2(0.5, 0.5),
+    ));
+
+    // A door on the way to the exit, which opens as the player nears it
+    let door = Object::new(
+        &mut app.physics_world,
+        RigidBodyBuilder::fixed()
+            .translation(vector![400.0, -160.0])
+            .build(),
+        PhysicsMaterial::Metal
+            .apply(ColliderBuilder::cuboid(4.0, 32.0))
+            .collision_groups(CollisionLayer::Station.groups())
+            .build(),
+        Texture2D::from_image(&Image::gen_image_color(
+            16,
+            128,
+            Color::new(0.55, 0.6, 0.65, 1.0),
+        )),
+        Vec::new(),
+        vec2(0.5, 0.5),
+    );
+    let door_id = door.id;
+    app.objects.push(door);
+
+    let zones = [
+        (
+            vec2(0.0, -160.0),
+            16.0,
+            TriggerAction::Checkpoint,
+            Some(Color::new(0.4, 1.0, 0.5, 1.0)),
         ),
         (
-            Isometry::<Real>::identity(),
-            SharedShape::convex_polyline(vec![
-                point![-2.0, 4.0],
-                point![-3.0, 3.0],
-                point![-3.0, -3.0],
-                point![-2.0, -4.0],
-            ])
-            .unwrap(),
+            vec2(480.0, -160.0),
+            24.0,
+            TriggerAction::LevelExit,
+            Some(Color::new(1.0, 0.9, 0.5, 1.0)),
         ),
         (
-            Isometry::<Real>::identity(),
-            SharedShape::convex_polyline(vec![
-                point![4.0, 3.0],
-                point![-1.0, 8.0],
-                point![-2.0, 8.0],
-                point![-2.0, -8.0],
-                point![-1.0, -8.0],
-                point![4.0, -3.0],
-            ])
-            .unwrap(),
+            vec2(-44.0, -8.0),
+            12.0,
+            TriggerAction::Message("ALIGN YOUR NOSE TO DOCK, F TO UNDOCK".to_string()),
+            None,
         ),
         (
-            Isometry::<Real>::identity(),
-            SharedShape::convex_polyline(vec![
-                point![8.0, 1.0],
-                point![6.0, 3.0],
-                point![4.0, 3.0],
-                point![4.0, -3.0],
-                point![6.0, -3.0],
-                point![8.0, -1.0],
-            ])
-            .unwrap(),
+            vec2(400.0, -160.0),
+            40.0,
+            TriggerAction::Door(door_id),
+            None,
         ),
-    ])
+    ];
+
+    for (position, radius, action, light) in zones {
+        let mut components = vec![Component::TriggerZone {
+            action,
+            player_only: true,
+            occupants: Vec::new(),
+        }];
+
+        if let Some(color) = light {
+            components.push(Component::Light {
+                offset: Vec2::ZERO,
+                color,
+                radius: radius * 3.0,
+            });
+        }
+
+        app.objects.push(Object::new(
+            &mut app.physics_world,
+            RigidBodyBuilder::fixed()
+                .translation(position.into())
+                .build(),
+            trigger_collider(SharedShape::ball(radius)).build(),
+            field_texture.clone(),
+            components,
+            vec2(0.5, 0.5),
+        ));
+    }
+
+    app.level_objects = app.objects.iter().map(|object| object.id).collect();
+
+    if let Ok(level) = Level::load(Level::PATH) {
+        level.apply(&mut app.physics_world, &mut app.objects, &app.level_objects);
+    }
+
+    loop {
+        app.check_fixed_tick();
+
+        app.frame_tick();
+
+        next_frame().await;
+    }
+}
+
+/// A shaded disc `size` pixels across, lit from the top left.
+fn make_planet_image(size: u16, color: Color) -> Image {
+    let mut image = Image::gen_image_color(size, size, BLANK);
+    let radius = size as f32 / 2.0;
+    let light = vec3(-0.5, -0.5, 0.7).normalize();
+
+    for y in 0..size as u32 {
+        for x in 0..size as u32 {
+            let offset = (vec2(x as f32, y as f32) + 0.5 - radius) / radius;
+            if offset.length_squared() > 1.0 {
+                continue;
+            }
+
+            let normal = offset.extend((1.0 - offset.length_squared()).sqrt());
+            let brightness = 0.25 + 0.75 * normal.dot(light).max(0.0);
+
+            image.set_pixel(
+                x,
+                y,
+                Color::new(
+                    color.r * brightness,
+                    color.g * brightness,
+                    color.b * brightness,
+                    1.0,
+                ),
+            );
+        }
+    }
+
+    image
 }
@@ -0,0 +1,246 @@
+use crate::collider_preset::ColliderPreset;
+use rapier2d::prelude::*;
+use std::collections::HashMap;
+
+/// A preset for how a collider's surface behaves in contacts. When two
+/// surfaces touch, the rule which comes last of `Average`, `Min`,
+/// `Multiply` and `Max` decides how their coefficients are combined.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhysicsMaterial {
+    /// Scrapes along with some friction and barely bounces.
+    Metal,
+    /// Grips and bounces anything which hits it.
+    Rubber,
+    /// Lets anything slide along it.
+    Ice,
+}
+
+impl PhysicsMaterial {
+    pub const MATERIALS: [Self; 3] = [Self::Metal, Self::Rubber, Self::Ice];
+
+    pub fn friction(self) -> f32 {
+        match self {
+            Self::Metal => 0.5,
+            Self::Rubber => 0.9,
+            Self::Ice => 0.02,
+        }
+    }
+
+    pub fn friction_combine_rule(self) -> CoefficientCombineRule {
+        match self {
+            Self::Metal => CoefficientCombineRule::Average,
+            Self::Rubber => CoefficientCombineRule::Average,
+            Self::Ice => CoefficientCombineRule::Min,
+        }
+    }
+
+    pub fn restitution(self) -> f32 {
+        match self {
+            Self::Metal => 0.1,
+            Self::Rubber => 0.85,
+            Self::Ice => 0.05,
+        }
+    }
+
+    pub fn restitution_combine_rule(self) -> CoefficientCombineRule {
+        match self {
+            Self::Metal => CoefficientCombineRule::Average,
+            Self::Rubber => CoefficientCombineRule::Max,
+            Self::Ice => CoefficientCombineRule::Average,
+        }
+    }
+
+    /// The mass per unit of area. Tile maps use their tile types'
+    /// densities instead.
+    pub fn density(self) -> f32 {
+        match self {
+            Self::Metal => 1.0,
+            Self::Rubber => 1.2,
+            Self::Ice => 0.9,
+        }
+    }
+
+    /// Gives a collider this material.
+    pub fn apply(self, collider: ColliderBuilder) -> ColliderBuilder {
+        collider
+            .friction(self.friction())
+            .friction_combine_rule(self.friction_combine_rule())
+            .restitution(self.restitution())
+            .restitution_combine_rule(self.restitution_combine_rule())
+            .density(self.density())
+    }
+}
+
+impl ColliderPreset for PhysicsMaterial {
+    const PRESETS: &'static [Self] = &Self::MATERIALS;
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Metal => "metal",
+            Self::Rubber => "rubber",
+            Self::Ice => "ice",
+        }
+    }
+
+    /// Gives the collider this material's surface, leaving its mass as it
+    /// is.
+    fn set(self, collider: &mut Collider) {
+        collider.set_friction(self.friction());
+        collider.set_friction_combine_rule(self.friction_combine_rule());
+        collider.set_restitution(self.restitution());
+        collider.set_restitution_combine_rule(self.restitution_combine_rule());
+    }
+
+    fn matches(self, collider: &Collider) -> bool {
+        collider.friction() == self.friction()
+            && collider.friction_combine_rule() == self.friction_combine_rule()
+            && collider.restitution() == self.restitution()
+            && collider.restitution_combine_rule() == self.restitution_combine_rule()
+    }
+}
+
+/// How strongly a combine rule takes precedence over the other rule when
+/// two surfaces touch. Rapier uses whichever rule comes last of
+/// `Average`, `Min`, `Multiply` and `Max`.
+fn priority(rule: CoefficientCombineRule) -> u8 {
+    match rule {
+        CoefficientCombineRule::Average => 0,
+        CoefficientCombineRule::Min => 1,
+        CoefficientCombineRule::Multiply => 2,
+        CoefficientCombineRule::Max => 3,
+    }
+}
+
+/// Combines two coefficients the way rapier does, using whichever rule
+/// has the higher priority.
+pub fn combine(
+    coefficient1: f32,
+    rule1: CoefficientCombineRule,
+    coefficient2: f32,
+    rule2: CoefficientCombineRule,
+) -> f32 {
+    let rule = if priority(rule1) >= priority(rule2) {
+        rule1
+    } else {
+        rule2
+    };
+
+    match rule {
+        CoefficientCombineRule::Average => (coefficient1 + coefficient2) / 2.0,
+        CoefficientCombineRule::Min => coefficient1.min(coefficient2),
+        CoefficientCombineRule::Multiply => coefficient1 * coefficient2,
+        CoefficientCombineRule::Max => coefficient1.max(coefficient2),
+    }
+}
+
+/// The friction and restitution of the part `subshape` of a collider,
+/// with their combine rules. Colliders without part materials use their
+/// own.
+fn surface(
+    collider: &Collider,
+    materials: Option<&Vec<PhysicsMaterial>>,
+    subshape: u32,
+) -> (f32, CoefficientCombineRule, f32, CoefficientCombineRule) {
+    match materials.and_then(|materials| materials.get(subshape as usize)) {
+        Some(material) => (
+            material.friction(),
+            material.friction_combine_rule(),
+            material.restitution(),
+            material.restitution_combine_rule(),
+        ),
+        None => (
+            collider.friction(),
+            collider.friction_combine_rule(),
+            collider.restitution(),
+            collider.restitution_combine_rule(),
+        ),
+    }
+}
+
+/// Replaces the friction and restitution of a contact's solver contacts
+/// with those of the parts touching, for compound colliders made of
+/// different materials like tile maps. `part_materials` holds the
+/// material of each subshape of those colliders.
+pub fn modify_contacts(
+    context: &mut ContactModificationContext,
+    part_materials: &HashMap<ColliderHandle, Vec<PhysicsMaterial>>,
+) {
+    let (friction1, friction_rule1, restitution1, restitution_rule1) = surface(
+        &context.colliders[context.collider1],
+        part_materials.get(&context.collider1),
+        context.manifold.subshape1,
+    );
+    let (friction2, friction_rule2, restitution2, restitution_rule2) = surface(
+        &context.colliders[context.collider2],
+        part_materials.get(&context.collider2),
+        context.manifold.subshape2,
+    );
+
+    let friction = combine(friction1, friction_rule1, friction2, friction_rule2);
+    let restitution = combine(
+        restitution1,
+        restitution_rule1,
+        restitution2,
+        restitution_rule2,
+    );
+
+    for contact in context.solver_contacts.iter_mut() {
+        contact.friction = friction;
+        contact.restitution = restitution;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_world::PhysicsWorld;
+
+    /// How fast a ball dropped onto the given part of a two part wall
+    /// bounces back up.
+    fn bounce_speed(part: usize) -> f32 {
+        let mut physics_world = PhysicsWorld::new();
+
+        let shapes = vec![
+            (
+                Isometry::translation(-8.0, 0.0),
+                SharedShape::cuboid(4.0, 4.0),
+            ),
+            (
+                Isometry::translation(8.0, 0.0),
+                SharedShape::cuboid(4.0, 4.0),
+            ),
+        ];
+        let (_, wall) = physics_world.add_rigidbody(
+            RigidBodyBuilder::fixed().build(),
+            ColliderBuilder::compound(shapes).build(),
+        );
+        physics_world
+            .set_part_materials(wall, vec![PhysicsMaterial::Metal, PhysicsMaterial::Rubber]);
+
+        let x = if part == 0 { -8.0 } else { 8.0 };
+        let (ball, _) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![x, -10.0])
+                .linvel(vector![0.0, 40.0])
+                .build(),
+            PhysicsMaterial::Metal
+                .apply(ColliderBuilder::ball(2.0))
+                .build(),
+        );
+
+        for _ in 0..30 {
+            physics_world.step();
+        }
+
+        -physics_world.rigid_body_set[ball].linvel().y
+    }
+
+    #[test]
+    fn parts_bounce_by_their_own_material() {
+        let metal = bounce_speed(0);
+        let rubber = bounce_speed(1);
+
+        assert!(metal < 10.0, "metal bounced at {}", metal);
+        assert!(rubber > 25.0, "rubber bounced at {}", rubber);
+    }
+}
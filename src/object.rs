@@ -1,15 +1,45 @@
+use crate::collision_layer::CollisionLayer;
 use crate::component::Component;
+use crate::flight_model::FlightModel;
 use crate::physics_world::PhysicsWorld;
+use crate::resources::Resources;
+use crate::tilemap::{TileMap, TileType};
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Identifies an object for its whole lifetime, even if it is removed
+/// and reinserted at a different index in `App::objects`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ObjectId(u64);
+
+impl ObjectId {
+    pub fn new() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for ObjectId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 pub struct Object {
+    pub id: ObjectId,
     pub rigid_body: RigidBodyHandle,
     pub collider: ColliderHandle,
     pub texture: Texture2D,
+    /// Drawn over the texture without lighting, for parts which glow.
+    pub emissive: Option<Texture2D>,
     pub size: Vec2,
     pub components: Vec<Component>,
     pub offset: Vec2,
+    /// The rigid body's position before the last fixed tick, which is
+    /// interpolated from when drawing.
+    pub previous_position: Isometry<Real>,
 }
 
 impl Object {
@@ -21,34 +51,223 @@ impl Object {
         components: Vec<Component>,
         offset: Vec2,
     ) -> Self {
+        let previous_position = *rigid_body.position();
         let (rigid_body, collider) = physics_world.add_rigidbody(rigid_body, collider);
 
         let size = texture.size() / 2.0;
 
         Self {
+            id: ObjectId::new(),
             rigid_body,
             collider,
             texture,
+            emissive: None,
             size,
             components,
             offset,
+            previous_position,
         }
     }
 
-    pub fn draw(&self, physics_world: &mut PhysicsWorld) {
+    /// The position to draw the object at, `interpolation` of the way
+    /// from its position before the last fixed tick to its current one.
+    pub fn interpolated_position(
+        &self,
+        physics_world: &PhysicsWorld,
+        interpolation: f32,
+    ) -> Isometry<Real> {
+        let position = physics_world.rigid_body_set[self.rigid_body].position();
+
+        self.previous_position
+            .lerp_slerp(position, interpolation.clamp(0.0, 1.0))
+    }
+
+    pub fn tile_map(&self) -> Option<&TileMap> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                Component::TileMap(tile_map) => Some(tile_map),
+                _ => None,
+            })
+    }
+
+    pub fn tile_map_mut(&mut self) -> Option<&mut TileMap> {
+        self.components
+            .iter_mut()
+            .find_map(|component| match component {
+                Component::TileMap(tile_map) => Some(tile_map),
+                _ => None,
+            })
+    }
+
+    /// The flight model of the object's `Motion` component, if it has one.
+    pub fn flight_model(&self) -> Option<&FlightModel> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                Component::Motion { flight_model, .. } => Some(flight_model),
+                _ => None,
+            })
+    }
+
+    pub fn resources(&self) -> Option<&Resources> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                Component::Resources(resources) => Some(resources),
+                _ => None,
+            })
+    }
+
+    pub fn resources_mut(&mut self) -> Option<&mut Resources> {
+        self.components
+            .iter_mut()
+            .find_map(|component| match component {
+                Component::Resources(resources) => Some(resources),
+                _ => None,
+            })
+    }
+
+    pub fn is_projectile(&self) -> bool {
+        self.components
+            .iter()
+            .any(|component| matches!(component, Component::Projectile { .. }))
+    }
+
+    /// The size of a single tile in the object's local space, if the
+    /// object has a tile map.
+    pub fn tile_size(&self) -> Option<Vec2> {
+        Some(self.size / self.tile_map()?.size().as_vec2())
+    }
+
+    /// The position of the top left corner of a tile in the object's
+    /// local space. Does not check if the tile is in bounds.
+    pub fn tile_to_local(&self, index: IVec2) -> Option<Vec2> {
+        Some(index.as_vec2() * self.tile_size()? - self.size * self.offset)
+    }
+
+    /// Finds the coordinate of the tile under a point in the object's
+    /// local space. The result may be out of bounds of the tile map.
+    pub fn local_to_tile(&self, point: Vec2) -> Option<IVec2> {
+        Some(
+            ((point + self.size * self.offset) / self.tile_size()?)
+                .floor()
+                .as_ivec2(),
+        )
+    }
+
+    /// Finds the coordinate of the tile under a point in world space.
+    /// The result may be out of bounds of the tile map.
+    pub fn world_to_tile(&self, physics_world: &PhysicsWorld, point: Vec2) -> Option<IVec2> {
         let rigid_body = &physics_world.rigid_body_set[self.rigid_body];
+        let local = rigid_body.position().inverse_transform_point(&point.into());
 
-        let position: Vec2 = (*rigid_body.translation()).into();
+        self.local_to_tile(local.into())
+    }
+
+    /// The centers of the thruster tiles in the object's local space.
+    pub fn thruster_positions(&self) -> Vec<Vec2> {
+        let (Some(tile_map), Some(tile_size)) = (self.tile_map(), self.tile_size()) else {
+            return Vec::new();
+        };
+
+        tile_map
+            .tiles_of_type(TileType::Thruster)
+            .into_iter()
+            .map(|index| self.tile_to_local(index.as_ivec2()).unwrap() + tile_size / 2.0)
+            .collect()
+    }
+
+    /// Where exhaust leaves the object in its local space, which is just
+    /// behind each thruster tile, or `emitter` if there are none.
+    pub fn exhaust_positions(&self, emitter: Vec2) -> Vec<Vec2> {
+        let mut exhausts = self.thruster_positions();
+
+        if let Some(tile_size) = self.tile_size() {
+            for exhaust in &mut exhausts {
+                exhaust.x -= tile_size.x / 2.0;
+            }
+        }
+
+        if exhausts.is_empty() {
+            exhausts.push(emitter);
+        }
+
+        exhausts
+    }
+
+    /// Makes a new dynamic object out of tiles split off of this
+    /// object's tile map, moving as if it were still attatched.
+    pub fn make_fragment(&self, physics_world: &mut PhysicsWorld, tile_map: TileMap) -> Object {
+        let tile_size = self.size / tile_map.size().as_vec2();
+        let origin = -self.size * self.offset;
+
+        let shape = tile_map
+            .make_shape(tile_size, origin)
+            .expect("Fragments should have tiles");
+        let mass_properties = tile_map.mass_properties(tile_size, origin);
+
+        let rigid_body = &physics_world.rigid_body_set[self.rigid_body];
+        let position = *rigid_body.position();
+
+        let offset = position * mass_properties.local_com - rigid_body.center_of_mass();
+        let linvel = rigid_body.linvel() + vector![-offset.y, offset.x] * rigid_body.angvel();
+
+        let rigid_body = RigidBodyBuilder::dynamic()
+            .position(position)
+            .linvel(linvel)
+            .angvel(rigid_body.angvel())
+            .ccd_enabled(true)
+            .build();
+
+        let texture_size = self.texture.size();
+
+        Object::new(
+            physics_world,
+            rigid_body,
+            ColliderBuilder::new(shape)
+                .mass_properties(mass_properties)
+                .collision_groups(CollisionLayer::Debris.groups())
+                .build(),
+            Texture2D::from_image(&Image::gen_image_color(
+                texture_size.x as u16,
+                texture_size.y as u16,
+                BLANK,
+            )),
+            vec![Component::TileMap(tile_map)],
+            self.offset,
+        )
+    }
+
+    pub fn draw(&self, physics_world: &PhysicsWorld, interpolation: f32) {
+        self.draw_texture(&self.texture, physics_world, interpolation);
+    }
+
+    pub fn draw_emissive(&self, physics_world: &PhysicsWorld, interpolation: f32) {
+        if let Some(emissive) = &self.emissive {
+            self.draw_texture(emissive, physics_world, interpolation);
+        }
+    }
+
+    /// Objects with a disabled collider, like open doors, aren't drawn.
+    fn draw_texture(&self, texture: &Texture2D, physics_world: &PhysicsWorld, interpolation: f32) {
+        if !physics_world.collider_set[self.collider].is_enabled() {
+            return;
+        }
+
+        let isometry = self.interpolated_position(physics_world, interpolation);
+
+        let position: Vec2 = isometry.translation.vector.into();
 
         draw_texture_ex(
-            &self.texture,
+            texture,
             position.x - self.size.x * self.offset.x,
             position.y - self.size.y * self.offset.y,
             WHITE,
             DrawTextureParams {
                 dest_size: Some(self.size),
                 source: None,
-                rotation: rigid_body.rotation().angle(),
+                rotation: isometry.rotation.angle(),
                 flip_x: false,
                 flip_y: false,
                 pivot: Some(position),
@@ -56,11 +275,10 @@ impl Object {
         )
     }
 
-    pub fn draw_debug(&self, physics_world: &mut PhysicsWorld) {
+    /// Marks the body's origin and its velocity from the center of mass.
+    /// Colliders are drawn by the debug overlay.
+    pub fn draw_debug(&self, physics_world: &PhysicsWorld) {
         let rigid_body = &physics_world.rigid_body_set[self.rigid_body];
-        let collider = &physics_world.collider_set[self.collider];
-
-        draw_compound_collider(collider, rigid_body.position());
 
         let position: Vec2 = (*rigid_body.translation()).into();
         draw_marker_at(position, 0.8, 0.2, GREEN);
@@ -71,33 +289,6 @@ impl Object {
     }
 }
 
-fn draw_compound_collider(collider: &Collider, rigod_body_position: &Isometry<Real>) -> Option<()> {
-    for (collider_position, shape) in collider.shape().as_compound()?.shapes() {
-        if let Some(shape) = shape.as_convex_polygon() {
-            let points: Vec<_> = shape
-                .points()
-                .into_iter()
-                .map(|point| {
-                    rigod_body_position.transform_point(&collider_position.transform_point(point))
-                })
-                .collect();
-
-            for point in &points {
-                draw_marker_at((*point).into(), 0.5, 0.1, MAGENTA)
-            }
-
-            for i in 0..points.len() {
-                let a = points[i];
-                let b = points[(i + 1) % points.len()];
-
-                draw_line(a.x, a.y, b.x, b.y, 0.1, MAGENTA);
-            }
-        }
-    }
-
-    Some(())
-}
-
 fn draw_marker_at(position: Vec2, radius: f32, bold: f32, color: Color) {
     draw_line(
         position.x + radius,
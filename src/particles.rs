@@ -0,0 +1,246 @@
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+use rapier2d::prelude::*;
+
+/// A fixed size pool of short lived visual effects. Once the pool is
+/// full, new particles replace the oldest ones. Particles snap to the
+/// texel grid and are drawn as a single tri-pixel triangle when smaller
+/// than a texel, or as whole texels otherwise.
+#[derive(Clone, Debug)]
+pub struct ParticleSystem {
+    pool: Vec<Particle>,
+    next: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Seconds since the particle was spawned.
+    pub age: f32,
+    pub lifetime: f32,
+    pub style: ParticleStyle,
+}
+
+/// How particles are spawned and how they change over their lifetime.
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleStyle {
+    /// The range of seconds particles live for.
+    pub lifetime: (f32, f32),
+    pub speed: (f32, f32),
+    /// Radians either side of the direction particles are emitted in.
+    pub spread: f32,
+    /// The fraction of the emitter's velocity particles keep.
+    pub inherit_velocity: f32,
+    /// The fraction of velocity lost per second.
+    pub drag: f32,
+    /// The colour at birth, halfway through its life, and at death.
+    pub colors: [Color; 3],
+    /// The size in texels at birth and at death.
+    pub sizes: [f32; 2],
+}
+
+impl ParticleStyle {
+    pub const EXHAUST: Self = Self {
+        lifetime: (0.2, 0.45),
+        speed: (20.0, 40.0),
+        spread: 0.25,
+        inherit_velocity: 1.0,
+        drag: 2.0,
+        colors: [
+            Color::new(1.0, 0.95, 0.7, 1.0),
+            Color::new(1.0, 0.5, 0.1, 0.8),
+            Color::new(0.4, 0.1, 0.05, 0.0),
+        ],
+        sizes: [1.5, 0.5],
+    };
+
+    pub const SPARK: Self = Self {
+        lifetime: (0.1, 0.35),
+        speed: (15.0, 50.0),
+        spread: 1.2,
+        inherit_velocity: 1.0,
+        drag: 4.0,
+        colors: [
+            Color::new(1.0, 1.0, 0.8, 1.0),
+            Color::new(1.0, 0.8, 0.3, 1.0),
+            Color::new(1.0, 0.4, 0.1, 0.0),
+        ],
+        sizes: [0.5, 0.5],
+    };
+
+    pub const EXPLOSION: Self = Self {
+        lifetime: (0.3, 0.8),
+        speed: (5.0, 30.0),
+        spread: std::f32::consts::PI,
+        inherit_velocity: 1.0,
+        drag: 3.0,
+        colors: [
+            Color::new(1.0, 1.0, 0.9, 1.0),
+            Color::new(1.0, 0.45, 0.1, 0.9),
+            Color::new(0.2, 0.2, 0.2, 0.0),
+        ],
+        sizes: [3.0, 1.0],
+    };
+}
+
+impl ParticleSystem {
+    pub const CAPACITY: usize = 4096;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            pool: Vec::with_capacity(capacity),
+            next: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, particle: Particle) {
+        if self.pool.len() < self.pool.capacity() {
+            self.pool.push(particle);
+        } else if !self.pool.is_empty() {
+            self.pool[self.next] = particle;
+            self.next = (self.next + 1) % self.pool.len();
+        }
+    }
+
+    /// Spawns `count` particles at `position` heading roughly in
+    /// `direction`, moving along with `velocity`.
+    pub fn emit(
+        &mut self,
+        style: &ParticleStyle,
+        position: Vec2,
+        velocity: Vec2,
+        direction: Vec2,
+        count: u32,
+    ) {
+        let angle = direction.y.atan2(direction.x);
+
+        for _ in 0..count {
+            let angle = angle + gen_range(-style.spread, style.spread);
+            let speed = gen_range(style.speed.0, style.speed.1);
+
+            self.spawn(Particle {
+                position,
+                velocity: velocity * style.inherit_velocity + Vec2::from_angle(angle) * speed,
+                age: 0.0,
+                lifetime: gen_range(style.lifetime.0, style.lifetime.1),
+                style: *style,
+            });
+        }
+    }
+
+    /// Emits particles from a point attached to a rigid body, given in
+    /// the body's local space, so that `rate` particles are spawned per
+    /// second on average. The particles inherit the velocity of the
+    /// body at that point.
+    pub fn emit_from(
+        &mut self,
+        style: &ParticleStyle,
+        rigid_body: &RigidBody,
+        offset: Vec2,
+        direction: Vec2,
+        rate: f32,
+        delta_time: f32,
+    ) {
+        let position = rigid_body.position().transform_point(&offset.into());
+        let velocity = rigid_body.velocity_at_point(&position);
+        let direction = rigid_body.position().transform_vector(&direction.into());
+
+        // Rates that don't divide evenly into a tick are made up for on average
+        let expected = rate * delta_time;
+        let count = expected as u32 + (gen_range(0.0, 1.0) < expected.fract()) as u32;
+
+        self.emit(
+            style,
+            position.into(),
+            velocity.into(),
+            direction.into(),
+            count,
+        );
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for particle in &mut self.pool {
+            if particle.age >= particle.lifetime {
+                continue;
+            }
+
+            particle.age += delta_time;
+            particle.velocity *= (1.0 - particle.style.drag * delta_time).max(0.0);
+            particle.position += particle.velocity * delta_time;
+        }
+    }
+
+    pub fn draw(&self) {
+        for particle in &self.pool {
+            if particle.age >= particle.lifetime {
+                continue;
+            }
+
+            let progress = particle.age / particle.lifetime;
+            let [start, middle, end] = particle.style.colors;
+
+            let color = if progress < 0.5 {
+                lerp_color(start, middle, progress * 2.0)
+            } else {
+                lerp_color(middle, end, progress * 2.0 - 1.0)
+            };
+
+            let [start_size, end_size] = particle.style.sizes;
+            let size = start_size + (end_size - start_size) * progress;
+
+            draw_particle(particle.position, size, color);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pool.clear();
+        self.next = 0;
+    }
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new(Self::CAPACITY)
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    Color::new(
+        a.r + (b.r - a.r) * t,
+        a.g + (b.g - a.g) * t,
+        a.b + (b.b - a.b) * t,
+        a.a + (b.a - a.a) * t,
+    )
+}
+
+/// Draws the triangle of the texel under `position` which it is closest
+/// to, matching how the tri-pixel material splits texels, or a square of
+/// whole texels for larger sizes.
+fn draw_particle(position: Vec2, size: f32, color: Color) {
+    if size >= 1.0 {
+        let size = size.round();
+        let corner = (position - size / 2.0).round();
+
+        draw_rectangle(corner.x, corner.y, size, size, color);
+        return;
+    }
+
+    let texel = position.floor();
+    let center = texel + 0.5;
+    let local = position - center;
+
+    let (a, b) = if local.x.abs() > local.y.abs() {
+        if local.x > 0.0 {
+            (vec2(1.0, 0.0), vec2(1.0, 1.0))
+        } else {
+            (vec2(0.0, 1.0), vec2(0.0, 0.0))
+        }
+    } else if local.y > 0.0 {
+        (vec2(1.0, 1.0), vec2(0.0, 1.0))
+    } else {
+        (vec2(0.0, 0.0), vec2(1.0, 0.0))
+    };
+
+    draw_triangle(center, texel + a, texel + b, color);
+}
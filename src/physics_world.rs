@@ -1,4 +1,8 @@
+use crate::collision_layer::OwnerFilter;
+use crate::material::PhysicsMaterial;
+use crate::trigger::{SensorEvent, SensorEventCollector};
 use rapier2d::prelude::*;
+use std::collections::HashMap;
 
 pub struct PhysicsWorld {
     pub physics_pipeline: PhysicsPipeline,
@@ -12,6 +16,13 @@ pub struct PhysicsWorld {
     pub multibody_joint_set: MultibodyJointSet,
     pub ccd_solver: CCDSolver,
     pub query_pipeline: Option<QueryPipeline>,
+    /// Colliders which started or stopped touching sensors during the
+    /// last step.
+    pub sensor_events: Vec<SensorEvent>,
+    /// The material of each subshape of compound colliders made of
+    /// different materials, like tile maps. Other colliders use their
+    /// own friction and restitution.
+    pub part_materials: HashMap<ColliderHandle, Vec<PhysicsMaterial>>,
 }
 
 impl PhysicsWorld {
@@ -31,10 +42,17 @@ impl PhysicsWorld {
             multibody_joint_set: MultibodyJointSet::new(),
             ccd_solver: CCDSolver::new(),
             query_pipeline: Some(QueryPipeline::new()),
+            sensor_events: Vec::new(),
+            part_materials: HashMap::new(),
         }
     }
 
     pub fn step(&mut self) {
+        let events = SensorEventCollector::default();
+        let hooks = Hooks {
+            part_materials: &self.part_materials,
+        };
+
         self.physics_pipeline.step(
             &vector![0.0, 0.0],
             &self.integration_parameters,
@@ -47,9 +65,11 @@ impl PhysicsWorld {
             &mut self.multibody_joint_set,
             &mut self.ccd_solver,
             self.query_pipeline.as_mut(),
-            &(),
-            &(),
-        )
+            &hooks,
+            &events,
+        );
+
+        self.sensor_events = events.into_events();
     }
 
     pub fn add_rigidbody(
@@ -66,4 +86,181 @@ impl PhysicsWorld {
 
         (rigid_body_handle, collider_handle)
     }
+
+    /// Gives each subshape of a compound collider its own material, in
+    /// the order of its shapes.
+    pub fn set_part_materials(
+        &mut self,
+        collider: ColliderHandle,
+        materials: Vec<PhysicsMaterial>,
+    ) {
+        if let Some(collider) = self.collider_set.get_mut(collider) {
+            collider
+                .set_active_hooks(collider.active_hooks() | ActiveHooks::MODIFY_SOLVER_CONTACTS);
+        }
+
+        self.part_materials.insert(collider, materials);
+    }
+
+    /// Finds a collider which contains `point`.
+    pub fn collider_at(&self, point: Point<Real>) -> Option<ColliderHandle> {
+        let mut found = None;
+
+        self.query_pipeline.as_ref()?.intersections_with_point(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &point,
+            QueryFilter::default(),
+            |collider| {
+                found = Some(collider);
+                false
+            },
+        );
+
+        found
+    }
+
+    /// Gives a collider new mass properties, such as after tiles are added
+    /// to it or removed from it, keeping each part of its rigid body moving
+    /// as it was. Rapier's linear velocity is that of the center of mass,
+    /// which moves with the new mass properties.
+    pub fn set_mass_properties(
+        &mut self,
+        collider: ColliderHandle,
+        mass_properties: MassProperties,
+    ) {
+        let Some(collider) = self.collider_set.get_mut(collider) else {
+            return;
+        };
+
+        collider.set_mass_properties(mass_properties);
+
+        let Some(rigid_body) = collider
+            .parent()
+            .and_then(|parent| self.rigid_body_set.get_mut(parent))
+        else {
+            return;
+        };
+
+        let center_of_mass = *rigid_body.center_of_mass();
+        rigid_body.recompute_mass_properties_from_colliders(&self.collider_set);
+
+        let offset = rigid_body.center_of_mass() - center_of_mass;
+        let linvel = rigid_body.linvel() + vector![-offset.y, offset.x] * rigid_body.angvel();
+        rigid_body.set_linvel(linvel, true);
+    }
+
+    /// Connects two rigid bodies with a joint.
+    pub fn add_joint(
+        &mut self,
+        rigid_body1: RigidBodyHandle,
+        rigid_body2: RigidBodyHandle,
+        joint: impl Into<GenericJoint>,
+    ) -> ImpulseJointHandle {
+        self.impulse_joint_set
+            .insert(rigid_body1, rigid_body2, joint, true)
+    }
+
+    pub fn remove_joint(&mut self, joint: ImpulseJointHandle) {
+        self.impulse_joint_set.remove(joint, true);
+    }
+
+    /// Whether `joint` still exists and is attatched to `rigid_body`.
+    /// Joints are removed along with either of their rigid bodies.
+    pub fn joint_connects(&self, joint: ImpulseJointHandle, rigid_body: RigidBodyHandle) -> bool {
+        self.impulse_joint_set
+            .get(joint)
+            .is_some_and(|joint| joint.body1 == rigid_body || joint.body2 == rigid_body)
+    }
+
+    /// Removes a rigid body along with its attatched colliders and
+    /// joints.
+    pub fn remove_rigidbody(&mut self, rigid_body: RigidBodyHandle) -> Option<RigidBody> {
+        if let Some(rigid_body) = self.rigid_body_set.get(rigid_body) {
+            for collider in rigid_body.colliders() {
+                self.part_materials.remove(collider);
+            }
+        }
+
+        self.rigid_body_set.remove(
+            rigid_body,
+            &mut self.island_manager,
+            &mut self.collider_set,
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
+            true,
+        )
+    }
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Filters out contacts with owners and gives compound colliders the
+/// materials of their parts.
+struct Hooks<'a> {
+    part_materials: &'a HashMap<ColliderHandle, Vec<PhysicsMaterial>>,
+}
+
+impl PhysicsHooks for Hooks<'_> {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        OwnerFilter.filter_contact_pair(context)
+    }
+
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        crate::material::modify_contacts(context, self.part_materials);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{Tile, TileMap, TileType};
+    use macroquad::prelude::{uvec2, Vec2};
+
+    #[test]
+    fn split_tile_map_keeps_moving_as_it_was() {
+        let mut physics_world = PhysicsWorld::new();
+
+        // Two islands, with a gap at the third tile
+        let mut tile_map = TileMap::with_images(uvec2(4, 1), Vec::new());
+        for x in [0, 1, 3] {
+            tile_map.set(uvec2(x, 0), Tile::new(TileType::Wall));
+        }
+
+        let tile_size = Vec2::splat(8.0);
+        let (rigid_body, collider) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic()
+                .linvel(vector![5.0, 0.0])
+                .angvel(2.0)
+                .build(),
+            ColliderBuilder::new(tile_map.make_shape(tile_size, Vec2::ZERO).unwrap())
+                .mass_properties(tile_map.mass_properties(tile_size, Vec2::ZERO))
+                .build(),
+        );
+        physics_world.step();
+
+        // On the first tile, which stays on the rigid body
+        let point = physics_world.rigid_body_set[rigid_body].position() * point![4.0, 4.0];
+        let before = physics_world.rigid_body_set[rigid_body].velocity_at_point(&point);
+
+        let island = tile_map.islands().pop().unwrap();
+        tile_map.split_off(&island);
+
+        let shape = tile_map.make_shape(tile_size, Vec2::ZERO).unwrap();
+        physics_world.collider_set[collider].set_shape(shape);
+        physics_world
+            .set_mass_properties(collider, tile_map.mass_properties(tile_size, Vec2::ZERO));
+
+        let after = physics_world.rigid_body_set[rigid_body].velocity_at_point(&point);
+        assert!(
+            (after - before).norm() < 1e-3,
+            "velocity changed from {:?} to {:?}",
+            before,
+            after
+        );
+    }
 }
@@ -0,0 +1,244 @@
+use crate::graphics::{draw_full, TargetCamera};
+use crate::settings::Settings;
+use macroquad::prelude::*;
+
+/// Runs a chain of full screen effects over the world before it's shown.
+/// The world is drawn into `scene_target`, then each enabled effect
+/// reads the output of the last, with the final one drawing to the
+/// screen.
+pub struct PostProcessing {
+    /// From 0 to 1, how strongly the damage vignette shows.
+    pub damage: f32,
+    /// Damage lost per second.
+    pub damage_decay: f32,
+    /// How bright a colour channel must be before it blooms.
+    pub bloom_threshold: f32,
+    pub bloom_strength: f32,
+    pub chromatic_aberration: f32,
+    pub crt_curvature: f32,
+    /// From 0 to 1, how dark the gaps between scanlines are.
+    pub scanlines: f32,
+    scene: RenderTarget,
+    targets: [RenderTarget; 2],
+    /// Half the size of the screen, since bloom is blurry anyway.
+    bloom_targets: [RenderTarget; 2],
+    bright_material: Material,
+    blur_material: Material,
+    bloom_material: Material,
+    chromatic_material: Material,
+    vignette_material: Material,
+    crt_material: Material,
+}
+
+impl PostProcessing {
+    /// How much each point of damage to the player adds to `damage`.
+    pub const DAMAGE_PER_POINT: f32 = 0.02;
+
+    pub fn new() -> Self {
+        let (scene, targets, bloom_targets) = Self::make_targets();
+
+        Self {
+            damage: 0.0,
+            damage_decay: 1.0,
+            bloom_threshold: 0.6,
+            bloom_strength: 0.8,
+            chromatic_aberration: 0.004,
+            crt_curvature: 0.03,
+            scanlines: 0.15,
+            scene,
+            targets,
+            bloom_targets,
+            bright_material: make_material(
+                include_str!("shaders/post_bright.glsl"),
+                &[("Threshold", UniformType::Float1)],
+                &[],
+            ),
+            blur_material: make_material(
+                include_str!("shaders/post_blur.glsl"),
+                &[("Direction", UniformType::Float2)],
+                &[],
+            ),
+            bloom_material: make_material(
+                include_str!("shaders/post_bloom.glsl"),
+                &[("Strength", UniformType::Float1)],
+                &["Bloom"],
+            ),
+            chromatic_material: make_material(
+                include_str!("shaders/post_chromatic.glsl"),
+                &[
+                    ("Strength", UniformType::Float1),
+                    ("Time", UniformType::Float1),
+                ],
+                &[],
+            ),
+            vignette_material: make_material(
+                include_str!("shaders/post_vignette.glsl"),
+                &[("Strength", UniformType::Float1)],
+                &[],
+            ),
+            crt_material: make_material(
+                include_str!("shaders/post_crt.glsl"),
+                &[
+                    ("Curvature", UniformType::Float1),
+                    ("Scanlines", UniformType::Float1),
+                ],
+                &[],
+            ),
+        }
+    }
+
+    pub fn add_damage(&mut self, amount: u32) {
+        self.damage = (self.damage + amount as f32 * Self::DAMAGE_PER_POINT).min(1.0);
+    }
+
+    /// The render target to draw the world into, or `None` if it should
+    /// be drawn straight to the screen.
+    pub fn scene_target(&mut self, settings: &Settings) -> Option<RenderTarget> {
+        if !settings.post_processing {
+            return None;
+        }
+
+        if self.scene.texture.size() != vec2(screen_width(), screen_height()) {
+            (self.scene, self.targets, self.bloom_targets) = Self::make_targets();
+        }
+
+        Some(self.scene.clone())
+    }
+
+    /// Draws the world from `scene_target` to the screen through each
+    /// enabled effect.
+    pub fn apply(&mut self, settings: &Settings, time: f32, delta_time: f32) {
+        self.damage = (self.damage - self.damage_decay * delta_time).max(0.0);
+
+        if !settings.post_processing {
+            return;
+        }
+
+        let mut current = self.scene.texture.clone();
+        let mut next = 0;
+
+        if settings.bloom {
+            self.bright_material
+                .set_uniform("Threshold", self.bloom_threshold);
+            pass(&self.bright_material, &current, &self.bloom_targets[0]);
+
+            self.blur_material.set_uniform("Direction", vec2(1.0, 0.0));
+            pass(
+                &self.blur_material,
+                &self.bloom_targets[0].texture,
+                &self.bloom_targets[1],
+            );
+
+            self.blur_material.set_uniform("Direction", vec2(0.0, 1.0));
+            pass(
+                &self.blur_material,
+                &self.bloom_targets[1].texture,
+                &self.bloom_targets[0],
+            );
+
+            self.bloom_material
+                .set_texture("Bloom", self.bloom_targets[0].texture.clone());
+            self.bloom_material
+                .set_uniform("Strength", self.bloom_strength);
+            pass(&self.bloom_material, &current, &self.targets[next]);
+
+            current = self.targets[next].texture.clone();
+            next ^= 1;
+        }
+
+        let mut effects = Vec::new();
+
+        if settings.chromatic_aberration {
+            self.chromatic_material
+                .set_uniform("Strength", self.chromatic_aberration);
+            self.chromatic_material.set_uniform("Time", time);
+            effects.push(&self.chromatic_material);
+        }
+
+        if settings.damage_vignette && self.damage > 0.0 {
+            self.vignette_material.set_uniform("Strength", self.damage);
+            effects.push(&self.vignette_material);
+        }
+
+        if settings.crt {
+            self.crt_material
+                .set_uniform("Curvature", self.crt_curvature);
+            self.crt_material.set_uniform("Scanlines", self.scanlines);
+            effects.push(&self.crt_material);
+        }
+
+        let Some(last) = effects.pop() else {
+            set_camera(&TargetCamera::full(None));
+            clear_background(BLACK);
+            draw_full(&current);
+            return;
+        };
+
+        for effect in effects {
+            pass(effect, &current, &self.targets[next]);
+
+            current = self.targets[next].texture.clone();
+            next ^= 1;
+        }
+
+        set_camera(&TargetCamera::full(None));
+        gl_use_material(last);
+        draw_full(&current);
+        gl_use_default_material();
+    }
+
+    fn make_targets() -> (RenderTarget, [RenderTarget; 2], [RenderTarget; 2]) {
+        let size = uvec2(screen_width() as u32, screen_height() as u32);
+
+        let make = |size: UVec2, filter| {
+            let target = render_target(size.x.max(1), size.y.max(1));
+            target.texture.set_filter(filter);
+            target
+        };
+
+        (
+            make(size, FilterMode::Nearest),
+            [
+                make(size, FilterMode::Nearest),
+                make(size, FilterMode::Nearest),
+            ],
+            [
+                make(size / 2, FilterMode::Linear),
+                make(size / 2, FilterMode::Linear),
+            ],
+        )
+    }
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pass(material: &Material, input: &Texture2D, output: &RenderTarget) {
+    set_camera(&TargetCamera::full(Some(output.clone())));
+    gl_use_material(material);
+    draw_full(input);
+    gl_use_default_material();
+}
+
+fn make_material(fragment: &str, uniforms: &[(&str, UniformType)], textures: &[&str]) -> Material {
+    let uniforms = uniforms
+        .iter()
+        .map(|&(name, uniform_type)| (name.to_string(), uniform_type))
+        .collect();
+
+    load_material(
+        ShaderSource::Glsl {
+            vertex: include_str!("shaders/simple_vertex.glsl"),
+            fragment,
+        },
+        MaterialParams {
+            uniforms,
+            textures: textures.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        },
+    )
+    .expect("Shader code should be valid")
+}
@@ -0,0 +1,155 @@
+/// The fuel, energy and heat of a ship. Fuel is burned by the engines
+/// and refilled at refuelling tiles, energy powers weapons and shields
+/// and regenerates, and heat builds up from thrusting and firing. A ship
+/// which reaches its heat capacity overheats, and can't thrust or fire
+/// until it has cooled down.
+#[derive(Clone, Debug)]
+pub struct Resources {
+    pub fuel: f32,
+    pub fuel_capacity: f32,
+    /// Fuel gained per second while touching a refuelling tile.
+    pub refuel_rate: f32,
+    pub energy: f32,
+    pub energy_capacity: f32,
+    /// Energy regained per second.
+    pub energy_regen: f32,
+    /// Energy drained for each point of damage the shields absorb. A
+    /// cost of 0 means the ship has no shields.
+    pub shield_cost: f32,
+    pub heat: f32,
+    pub heat_capacity: f32,
+    /// Heat lost per second.
+    pub cooling: f32,
+    /// Heat gained per second with the main engine at full thrust. This
+    /// needs to be more than `cooling` for thrusting to overheat.
+    pub thrust_heat: f32,
+    pub overheated: bool,
+    /// Whether the ship touched a refuelling tile during the last fixed
+    /// tick.
+    pub refuelling: bool,
+}
+
+impl Resources {
+    /// An overheated ship can act again once its heat is below this
+    /// fraction of its capacity.
+    pub const COOLED_FRACTION: f32 = 0.5;
+
+    pub const SHIP: Self = Self {
+        fuel: 100.0,
+        fuel_capacity: 100.0,
+        refuel_rate: 20.0,
+        energy: 100.0,
+        energy_capacity: 100.0,
+        energy_regen: 10.0,
+        shield_cost: 0.5,
+        heat: 0.0,
+        heat_capacity: 100.0,
+        cooling: 15.0,
+        thrust_heat: 25.0,
+        overheated: false,
+        refuelling: false,
+    };
+
+    pub fn can_thrust(&self) -> bool {
+        self.fuel > 0.0 && !self.overheated
+    }
+
+    pub fn burn_fuel(&mut self, amount: f32) {
+        self.fuel = (self.fuel - amount).max(0.0);
+    }
+
+    /// Uses `energy` if there's enough of it and the ship isn't
+    /// overheated, returning whether it was used.
+    pub fn use_energy(&mut self, energy: f32) -> bool {
+        if self.overheated || self.energy < energy {
+            return false;
+        }
+
+        self.energy -= energy;
+        true
+    }
+
+    pub fn add_heat(&mut self, heat: f32) {
+        self.heat = (self.heat + heat).min(self.heat_capacity);
+
+        if self.heat >= self.heat_capacity {
+            self.overheated = true;
+        }
+    }
+
+    /// Absorbs as much of `damage` as the ship's energy allows, returning
+    /// the damage which gets through.
+    pub fn shield(&mut self, damage: u32) -> u32 {
+        if self.shield_cost <= 0.0 {
+            return damage;
+        }
+
+        let absorbed = ((self.energy / self.shield_cost) as u32).min(damage);
+        self.energy -= absorbed as f32 * self.shield_cost;
+
+        damage - absorbed
+    }
+
+    /// Regenerates energy, cools down and refuels over `delta_time`.
+    pub fn update(&mut self, delta_time: f32) {
+        if self.refuelling {
+            self.fuel = (self.fuel + self.refuel_rate * delta_time).min(self.fuel_capacity);
+        }
+
+        self.energy = (self.energy + self.energy_regen * delta_time).min(self.energy_capacity);
+        self.heat = (self.heat - self.cooling * delta_time).max(0.0);
+
+        if self.overheated && self.heat < self.heat_capacity * Self::COOLED_FRACTION {
+            self.overheated = false;
+        }
+    }
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self::SHIP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+
+    #[test]
+    fn sustained_thrust_overheats_then_cools_down() {
+        let mut resources = Resources::SHIP;
+        let mut ticks = 0;
+
+        while !resources.overheated {
+            resources.add_heat(resources.thrust_heat * App::FIXED_DELTA_TIME);
+            resources.update(App::FIXED_DELTA_TIME);
+
+            ticks += 1;
+            assert!(
+                ticks as f32 * App::FIXED_DELTA_TIME < 30.0,
+                "still at {} heat after 30s",
+                resources.heat
+            );
+        }
+
+        assert!(!resources.can_thrust());
+
+        while resources.overheated {
+            resources.update(App::FIXED_DELTA_TIME);
+        }
+
+        assert!(resources.can_thrust());
+    }
+
+    #[test]
+    fn shields_without_cost_absorb_nothing() {
+        let mut resources = Resources {
+            shield_cost: 0.0,
+            ..Resources::SHIP
+        };
+
+        assert_eq!(resources.shield(30), 30);
+        assert_eq!(resources.energy, Resources::SHIP.energy);
+    }
+}
@@ -0,0 +1,33 @@
+/// Options which can be changed while playing.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Draws the world to an offscreen render target and runs the
+    /// enabled effects over it. When off, the world is drawn straight to
+    /// the screen and none of the effects below are used.
+    pub post_processing: bool,
+    pub bloom: bool,
+    /// Splits the colour channels towards the edges of the screen.
+    pub chromatic_aberration: bool,
+    /// Scanlines and a slight curve, like an old monitor.
+    pub crt: bool,
+    /// Reddens the edges of the screen when the player is damaged.
+    pub damage_vignette: bool,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            post_processing: true,
+            bloom: true,
+            chromatic_aberration: true,
+            crt: true,
+            damage_vignette: true,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
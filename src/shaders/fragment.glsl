@@ -4,6 +4,8 @@ precision lowp float;
 varying vec2 uv;
 
 uniform sampler2D Texture;
+uniform sampler2D LightMap;
+uniform float Lit;
 
 void main()
 {
@@ -20,5 +22,30 @@ void main()
     pixel.x += float(pixel_cord.y < pixel_cord.x);
     pixel.y += float(pixel_cord.y > 1.0 - pixel_cord.x);
 
-    gl_FragColor = texture2D(Texture, pixel / texture_size);
+    vec4 color = texture2D(Texture, pixel / texture_size);
+
+    if (Lit > 0.5) {
+        // Light is sampled at the centroid of the triangle, so that each
+        // tri-pixel is lit evenly
+        vec2 offset = pixel_cord - 0.5;
+        vec2 direction = abs(offset.x) > abs(offset.y)
+            ? vec2(sign(offset.x), 0.0)
+            : vec2(0.0, sign(offset.y));
+        vec2 centroid = floor(texture_cord / 2.0) * 2.0 + (0.5 + direction / 3.0) * 2.0;
+
+        // Converts the offset to the centroid from texture to screen space
+        mat2 to_texture = mat2(dFdx(texture_cord), dFdy(texture_cord));
+        float determinant = to_texture[0][0] * to_texture[1][1] - to_texture[1][0] * to_texture[0][1];
+        mat2 to_screen = mat2(
+            to_texture[1][1], -to_texture[0][1],
+            -to_texture[1][0], to_texture[0][0]
+        ) / determinant;
+
+        vec2 screen = gl_FragCoord.xy + to_screen * (centroid - texture_cord);
+        vec2 light_map_size = vec2(textureSize(LightMap, 0));
+
+        color.rgb *= texture2D(LightMap, screen / light_map_size).rgb;
+    }
+
+    gl_FragColor = color;
 }
@@ -0,0 +1,12 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+
+void main()
+{
+    gl_FragColor = texture2D(Texture, uv) * color;
+}
@@ -0,0 +1,15 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform sampler2D Bloom;
+uniform float Strength;
+
+void main()
+{
+    vec3 color = texture2D(Texture, uv).rgb + texture2D(Bloom, uv).rgb * Strength;
+
+    gl_FragColor = vec4(color, 1.0);
+}
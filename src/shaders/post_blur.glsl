@@ -0,0 +1,20 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec2 Direction;
+
+void main()
+{
+    vec2 step = Direction / vec2(textureSize(Texture, 0));
+
+    vec3 color = texture2D(Texture, uv).rgb * 0.227;
+    color += texture2D(Texture, uv + step * 1.385).rgb * 0.316;
+    color += texture2D(Texture, uv - step * 1.385).rgb * 0.316;
+    color += texture2D(Texture, uv + step * 3.231).rgb * 0.070;
+    color += texture2D(Texture, uv - step * 3.231).rgb * 0.070;
+
+    gl_FragColor = vec4(color, 1.0);
+}
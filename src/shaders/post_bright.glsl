@@ -0,0 +1,15 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform float Threshold;
+
+void main()
+{
+    vec4 color = texture2D(Texture, uv);
+    float brightness = max(color.r, max(color.g, color.b));
+
+    gl_FragColor = vec4(color.rgb * max(brightness - Threshold, 0.0) / max(brightness, 0.0001), 1.0);
+}
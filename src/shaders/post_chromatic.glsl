@@ -0,0 +1,21 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform float Strength;
+uniform float Time;
+
+void main()
+{
+    // The split wobbles slightly over time
+    vec2 offset = (uv - 0.5) * Strength * (1.0 + 0.3 * sin(Time * 1.7));
+
+    gl_FragColor = vec4(
+        texture2D(Texture, uv + offset).r,
+        texture2D(Texture, uv).g,
+        texture2D(Texture, uv - offset).b,
+        1.0
+    );
+}
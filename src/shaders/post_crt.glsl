@@ -0,0 +1,26 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform float Curvature;
+uniform float Scanlines;
+
+void main()
+{
+    vec2 centered = uv * 2.0 - 1.0;
+    centered *= 1.0 + dot(centered, centered) * Curvature;
+    vec2 curved = centered * 0.5 + 0.5;
+
+    if (curved.x < 0.0 || curved.x > 1.0 || curved.y < 0.0 || curved.y > 1.0) {
+        gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec3 color = texture2D(Texture, curved).rgb;
+    float scanline = 1.0 - Scanlines * (0.5 + 0.5 * cos(gl_FragCoord.y * 3.14159));
+    float vignette = 1.0 - dot(centered, centered) * 0.15;
+
+    gl_FragColor = vec4(color * scanline * vignette, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 130
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform float Strength;
+
+void main()
+{
+    vec3 color = texture2D(Texture, uv).rgb;
+    float edge = smoothstep(0.4, 1.0, length(uv * 2.0 - 1.0));
+
+    gl_FragColor = vec4(mix(color, vec3(0.8, 0.05, 0.05), edge * Strength), 1.0);
+}
@@ -0,0 +1,247 @@
+use macroquad::prelude::*;
+use rapier2d::prelude::*;
+use std::collections::HashMap;
+
+/// Pixels with at least this much alpha are solid.
+pub const ALPHA_THRESHOLD: f32 = 0.5;
+
+/// Makes a compound of convex polygons covering the opaque pixels of an
+/// object's sprite, matching how the tri-pixel material draws it: each
+/// 2x2 group of pixels is a texel split along its diagonals into four
+/// triangles, with each triangle taking the colour of one pixel. The
+/// shape is scaled to `size` and placed the same way as the object's
+/// texture using `offset`. Returns `None` if no pixels are opaque.
+pub fn make_sprite_shape(image: &Image, size: Vec2, offset: Vec2) -> Option<SharedShape> {
+    let image_size = uvec2(image.width as u32, image.height as u32);
+
+    // Points are kept at twice the texel scale, so the centers of texels
+    // are whole numbers and edges can be compared exactly
+    let texel_scale = size / (image_size.as_vec2() / 2.0) / 2.0;
+    let origin = -size * offset;
+
+    let polygons = merge_convex(sprite_triangles(image));
+    if polygons.is_empty() {
+        return None;
+    }
+
+    let shapes = polygons
+        .into_iter()
+        .filter_map(|polygon| {
+            let points = polygon
+                .iter()
+                .map(|point| (origin + point.as_vec2() * texel_scale).into())
+                .collect();
+
+            Some((Isometry::identity(), SharedShape::convex_polyline(points)?))
+        })
+        .collect();
+
+    Some(SharedShape::compound(shapes))
+}
+
+/// The triangles of each opaque pixel, wound the same way, in doubled
+/// texel coordinates.
+fn sprite_triangles(image: &Image) -> Vec<Vec<IVec2>> {
+    let texels = (uvec2(image.width as u32, image.height as u32) + 1) / 2;
+    let mut triangles = Vec::new();
+
+    for y in 0..texels.y {
+        for x in 0..texels.x {
+            let corner = ivec2(x as i32, y as i32) * 2;
+            let [top_left, top_right, bottom_right, bottom_left] = [
+                corner,
+                corner + ivec2(2, 0),
+                corner + ivec2(2, 2),
+                corner + ivec2(0, 2),
+            ];
+            let center = corner + IVec2::ONE;
+
+            // Which pixel of the group each triangle shows, as in the
+            // tri-pixel fragment shader
+            let sides = [
+                (uvec2(1, 0), [top_left, top_right, center]),
+                (uvec2(1, 1), [top_right, bottom_right, center]),
+                (uvec2(0, 1), [bottom_right, bottom_left, center]),
+                (uvec2(0, 0), [bottom_left, top_left, center]),
+            ];
+
+            for (pixel, triangle) in sides {
+                let pixel = uvec2(x, y) * 2 + pixel;
+
+                if pixel.x < image.width as u32
+                    && pixel.y < image.height as u32
+                    && image.get_pixel(pixel.x, pixel.y).a >= ALPHA_THRESHOLD
+                {
+                    triangles.push(triangle.to_vec());
+                }
+            }
+        }
+    }
+
+    triangles
+}
+
+/// Repeatedly joins polygons which share an edge while the result stays
+/// convex. Earlier polygons grow first, so sprites become mostly
+/// horizontal strips.
+fn merge_convex(polygons: Vec<Vec<IVec2>>) -> Vec<Vec<IVec2>> {
+    let mut polygons: Vec<Option<Vec<IVec2>>> = polygons.into_iter().map(Some).collect();
+    let mut edges = HashMap::new();
+
+    for (index, polygon) in polygons.iter().enumerate() {
+        for edge in polygon_edges(polygon.as_ref().unwrap()) {
+            edges.insert(edge, index);
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for index in 0..polygons.len() {
+            while let Some(polygon) = &polygons[index] {
+                let Some((other, merged)) = polygon_edges(polygon).find_map(|(a, b)| {
+                    let &other = edges.get(&(b, a))?;
+                    let merged = merge(polygon, polygons[other].as_ref()?, a, b)?;
+
+                    Some((other, merged))
+                }) else {
+                    break;
+                };
+
+                for removed in [index, other] {
+                    for edge in polygon_edges(polygons[removed].as_ref().unwrap()) {
+                        edges.remove(&edge);
+                    }
+                }
+
+                for edge in polygon_edges(&merged) {
+                    edges.insert(edge, index);
+                }
+
+                polygons[other] = None;
+                polygons[index] = Some(merged);
+                changed = true;
+            }
+        }
+    }
+
+    polygons.into_iter().flatten().collect()
+}
+
+fn polygon_edges(polygon: &[IVec2]) -> impl Iterator<Item = (IVec2, IVec2)> + '_ {
+    (0..polygon.len()).map(|i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+}
+
+/// Joins `first` and `second` along the edge from `a` to `b` of `first`,
+/// if the result is convex.
+fn merge(first: &[IVec2], second: &[IVec2], a: IVec2, b: IVec2) -> Option<Vec<IVec2>> {
+    let start = first.iter().position(|&point| point == b)?;
+    let joined = second.iter().position(|&point| point == a)?;
+
+    // All of `first` from `b` around to `a`, then `second` between `a`
+    // and `b`
+    let mut merged: Vec<IVec2> = (0..first.len())
+        .map(|i| first[(start + i) % first.len()])
+        .collect();
+    merged.extend((1..second.len() - 1).map(|i| second[(joined + i) % second.len()]));
+
+    // Drops points in the middle of straight edges
+    let mut i = 0;
+    while i < merged.len() && merged.len() > 3 {
+        let previous = merged[(i + merged.len() - 1) % merged.len()];
+        let next = merged[(i + 1) % merged.len()];
+        let (incoming, outgoing) = (merged[i] - previous, next - merged[i]);
+
+        if incoming.perp_dot(outgoing) == 0 && incoming.dot(outgoing) > 0 {
+            merged.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    let convex = (0..merged.len()).all(|i| {
+        let previous = merged[(i + merged.len() - 1) % merged.len()];
+        let next = merged[(i + 1) % merged.len()];
+
+        (merged[i] - previous).perp_dot(next - merged[i]) > 0
+    });
+
+    convex.then_some(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape_area(shape: &SharedShape) -> f32 {
+        shape.mass_properties(1.0).mass()
+    }
+
+    /// Each opaque pixel is a quarter of a texel.
+    fn opaque_area(image: &Image, size: Vec2) -> f32 {
+        let opaque = image
+            .get_image_data()
+            .iter()
+            .filter(|pixel| pixel[3] as f32 / 255.0 >= ALPHA_THRESHOLD)
+            .count();
+        let texel = size / (vec2(image.width as f32, image.height as f32) / 2.0);
+
+        opaque as f32 * texel.x * texel.y / 4.0
+    }
+
+    fn ship_image() -> Image {
+        Image::from_file_with_format(
+            include_bytes!("../assets/ship_active.png"),
+            Some(ImageFormat::Png),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ship_area_matches_opaque_pixels() {
+        let image = ship_image();
+        let size = vec2(image.width as f32, image.height as f32) / 2.0;
+        let shape = make_sprite_shape(&image, size, vec2(0.5, 0.5)).unwrap();
+
+        let expected = opaque_area(&image, size);
+        assert!((shape_area(&shape) - expected).abs() < expected * 0.01);
+    }
+
+    #[test]
+    fn scaled_area_matches_opaque_pixels() {
+        let image = ship_image();
+        let size = vec2(40.0, 24.0);
+        let shape = make_sprite_shape(&image, size, Vec2::ZERO).unwrap();
+
+        let expected = opaque_area(&image, size);
+        assert!((shape_area(&shape) - expected).abs() < expected * 0.01);
+
+        let aabb = shape.compute_local_aabb();
+        assert!(aabb.mins.x >= -0.001 && aabb.mins.y >= -0.001);
+        assert!(aabb.maxs.x <= size.x + 0.001 && aabb.maxs.y <= size.y + 0.001);
+    }
+
+    #[test]
+    fn diagonal_pixels_make_triangles() {
+        // One texel with its top and right triangles opaque is half of a
+        // square, cut along the diagonal
+        let mut image = Image::gen_image_color(2, 2, BLANK);
+        image.set_pixel(1, 0, WHITE);
+        image.set_pixel(1, 1, WHITE);
+
+        let shape = make_sprite_shape(&image, Vec2::ONE, Vec2::ZERO).unwrap();
+        let parts = shape.as_compound().unwrap().shapes();
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].1.as_convex_polygon().unwrap().points().len(), 3);
+        assert!((shape_area(&shape) - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn transparent_image_has_no_shape() {
+        let image = Image::gen_image_color(4, 4, BLANK);
+
+        assert!(make_sprite_shape(&image, vec2(2.0, 2.0), Vec2::ZERO).is_none());
+    }
+}
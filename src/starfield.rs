@@ -0,0 +1,290 @@
+use crate::graphics::TargetCamera;
+use macroquad::prelude::*;
+
+/// A procedural background of stars and nebulae. Stars are placed in a
+/// grid of cells per layer, each seeded from its coordinates, so the
+/// same seed always gives the same sky. Positions are computed in `f64`
+/// and hashed with integer math so they are consistent across platforms.
+#[derive(Clone, Debug)]
+pub struct Starfield {
+    pub seed: u64,
+    pub layers: Vec<StarLayer>,
+    pub nebulae: NebulaLayer,
+    /// The background is drawn as if the view were at most this many
+    /// world units tall, so zooming out doesn't fill the screen with
+    /// tiny stars.
+    pub max_view_height: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct StarLayer {
+    /// How fast the layer moves relative to the world, where 0 is
+    /// infinitely far away and 1 moves with the objects.
+    pub parallax: f64,
+    pub cell_size: f64,
+    pub stars_per_cell: u32,
+    pub min_size: f32,
+    pub max_size: f32,
+    pub brightness: f32,
+    /// The fraction of brightness lost at the dimmest point of a twinkle.
+    pub twinkle: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct NebulaLayer {
+    pub parallax: f64,
+    pub cell_size: f64,
+    /// The chance from 0 to 1 of each cell having a nebula.
+    pub density: f64,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub opacity: f32,
+}
+
+impl Starfield {
+    const STAR_COLORS: [Color; 5] = [
+        Color::new(0.7, 0.8, 1.0, 1.0),
+        Color::new(0.9, 0.95, 1.0, 1.0),
+        Color::new(1.0, 1.0, 1.0, 1.0),
+        Color::new(1.0, 0.95, 0.8, 1.0),
+        Color::new(1.0, 0.8, 0.6, 1.0),
+    ];
+
+    const NEBULA_COLORS: [Color; 4] = [
+        Color::new(0.4, 0.2, 0.6, 1.0),
+        Color::new(0.2, 0.3, 0.7, 1.0),
+        Color::new(0.1, 0.5, 0.5, 1.0),
+        Color::new(0.6, 0.2, 0.3, 1.0),
+    ];
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            layers: vec![
+                StarLayer {
+                    parallax: 0.1,
+                    cell_size: 64.0,
+                    stars_per_cell: 3,
+                    min_size: 0.3,
+                    max_size: 0.6,
+                    brightness: 0.5,
+                    twinkle: 0.5,
+                },
+                StarLayer {
+                    parallax: 0.3,
+                    cell_size: 96.0,
+                    stars_per_cell: 3,
+                    min_size: 0.5,
+                    max_size: 0.9,
+                    brightness: 0.75,
+                    twinkle: 0.3,
+                },
+                StarLayer {
+                    parallax: 0.6,
+                    cell_size: 160.0,
+                    stars_per_cell: 2,
+                    min_size: 0.8,
+                    max_size: 1.4,
+                    brightness: 1.0,
+                    twinkle: 0.15,
+                },
+            ],
+            nebulae: NebulaLayer {
+                parallax: 0.05,
+                cell_size: 384.0,
+                density: 0.35,
+                min_radius: 60.0,
+                max_radius: 180.0,
+                opacity: 0.05,
+            },
+            max_view_height: 256.0,
+        }
+    }
+
+    /// Draws the background behind everything else into `render_target`,
+    /// or the screen if there is none, leaving `camera` set afterwards.
+    pub fn draw(&self, camera: &Camera2D, render_target: Option<RenderTarget>, time: f64) {
+        let min_zoom = 2.0 / self.max_view_height;
+        let scale = (min_zoom / camera.zoom.y).max(1.0);
+
+        let background_camera = Camera2D {
+            zoom: camera.zoom * scale,
+            target: camera.target,
+            rotation: camera.rotation,
+            ..Default::default()
+        };
+
+        set_camera(&TargetCamera::new(
+            &background_camera,
+            render_target.clone(),
+        ));
+
+        let half_extent = Vec2::ONE / background_camera.zoom.abs();
+        let radius = half_extent.length() as f64;
+        let center = camera.target.as_dvec2();
+
+        self.draw_nebulae(center, radius);
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            self.draw_layer(i as u64, layer, center, radius, time);
+        }
+
+        set_camera(&TargetCamera::new(camera, render_target));
+    }
+
+    fn draw_nebulae(&self, center: DVec2, radius: f64) {
+        let nebulae = &self.nebulae;
+
+        // Nebulae are large, so neighbouring cells may overlap the view
+        let margin = nebulae.max_radius as f64 * 1.5;
+
+        for (cell, mut random) in cells(
+            self.seed ^ 0x6e65_6275_6c61,
+            center * nebulae.parallax,
+            radius + margin,
+            nebulae.cell_size,
+        ) {
+            if random.next_f64() >= nebulae.density {
+                continue;
+            }
+
+            let origin = (cell + random.next_dvec2()) * nebulae.cell_size;
+            let position = center + origin - center * nebulae.parallax;
+            let radius = lerp(nebulae.min_radius, nebulae.max_radius, random.next_f32());
+            let color = Self::NEBULA_COLORS[random.next_index(Self::NEBULA_COLORS.len())];
+
+            // Overlapping translucent circles fake a soft glow
+            for _ in 0..4 {
+                let blob = position + (random.next_dvec2() - 0.5) * radius as f64;
+                let blob_radius = radius * lerp(0.4, 0.8, random.next_f32());
+
+                for ring in 1..=6 {
+                    let ring_radius = blob_radius * ring as f32 / 6.0;
+                    draw_circle(
+                        blob.x as f32,
+                        blob.y as f32,
+                        ring_radius,
+                        Color {
+                            a: nebulae.opacity,
+                            ..color
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn draw_layer(&self, index: u64, layer: &StarLayer, center: DVec2, radius: f64, time: f64) {
+        for (cell, mut random) in cells(
+            self.seed.wrapping_add(index.wrapping_mul(0x9e37_79b9)),
+            center * layer.parallax,
+            radius,
+            layer.cell_size,
+        ) {
+            for _ in 0..layer.stars_per_cell {
+                let origin = (cell + random.next_dvec2()) * layer.cell_size;
+                let position = center + origin - center * layer.parallax;
+
+                let size = lerp(layer.min_size, layer.max_size, random.next_f32());
+                let color = Self::STAR_COLORS[random.next_index(Self::STAR_COLORS.len())];
+                let rotation = random.next_f32() * std::f32::consts::TAU;
+
+                let frequency = lerp(0.5, 3.0, random.next_f32()) as f64;
+                let phase = random.next_f64() * std::f64::consts::TAU;
+                let twinkle = ((time * frequency + phase).sin() * 0.5 + 0.5) as f32;
+                let brightness = layer.brightness * (1.0 - layer.twinkle * twinkle);
+
+                draw_star(
+                    position,
+                    size,
+                    rotation,
+                    Color {
+                        a: brightness,
+                        ..color
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Every grid cell within `radius` of `center`, with a random number
+/// generator seeded from the cell's coordinates.
+fn cells(
+    seed: u64,
+    center: DVec2,
+    radius: f64,
+    cell_size: f64,
+) -> impl Iterator<Item = (DVec2, SplitMix64)> {
+    let min = ((center - radius) / cell_size).floor();
+    let max = ((center + radius) / cell_size).floor();
+
+    let (min_x, min_y) = (min.x as i64, min.y as i64);
+    let (max_x, max_y) = (max.x as i64, max.y as i64);
+
+    (min_x..=max_x).flat_map(move |x| {
+        (min_y..=max_y).map(move |y| {
+            let cell_seed = seed
+                ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+
+            (dvec2(x as f64, y as f64), SplitMix64(cell_seed))
+        })
+    })
+}
+
+/// A tiny deterministic random number generator.
+#[derive(Clone, Copy, Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// From 0 up to but not including 1.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.next_f64() as f32
+    }
+
+    fn next_dvec2(&mut self) -> DVec2 {
+        dvec2(self.next_f64(), self.next_f64())
+    }
+
+    fn next_index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn draw_star(position: DVec2, size: f32, rotation: f32, color: Color) {
+    draw_rectangle_ex(
+        position.x as f32,
+        position.y as f32,
+        size,
+        size,
+        DrawRectangleParams {
+            offset: Vec2::splat(0.5),
+            rotation,
+            color,
+        },
+    )
+}
@@ -1,5 +1,7 @@
+use crate::material::PhysicsMaterial;
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
     ops::{Index, IndexMut},
@@ -9,23 +11,53 @@ use std::{
 pub struct TileMap {
     pub contents: Vec<Vec<Tile>>,
     pub updates: HashSet<UVec2>,
-    pub tile_images: Vec<Option<Image>>,
+    /// Set when a tile is changed, so that the collider of the object
+    /// can be rebuilt.
+    pub shape_changed: bool,
+    /// Set when a tile is removed, so that the tile map can be checked
+    /// for parts which are no longer connected.
+    pub check_connectivity: bool,
+    /// The total max health of the tiles the map was built with, so that
+    /// destroyed tiles still count against its hull. Set by `reset_hull`.
+    pub max_hull: u32,
+    /// A mass tuned with the `Inspector`, which the collider is given
+    /// instead of the tiles' mass whenever it is rebuilt.
+    pub tuned_mass: Option<f32>,
+    /// The images of each tile type, for each of its damage stages.
+    pub tile_images: Vec<Option<Vec<Image>>>,
+    pub empty_image: Image,
 }
 
 impl TileMap {
+    /// The impulse a contact must exceed before it damages the tile it
+    /// touches.
+    pub const COLLISION_DAMAGE_THRESHOLD: f32 = 1000.0;
+    pub const COLLISION_DAMAGE_PER_IMPULSE: f32 = 0.05;
+    /// How much collision damage shakes the camera.
+    pub const TRAUMA_PER_DAMAGE: f32 = 0.005;
+
     pub async fn new(size: UVec2) -> Self {
+        Self::with_images(size, TileType::load_images().await)
+    }
+
+    /// Makes an empty tile map which draws its tiles with `tile_images`,
+    /// as loaded by `TileType::load_images`.
+    pub fn with_images(size: UVec2, tile_images: Vec<Option<Vec<Image>>>) -> Self {
         Self {
             contents: (0..size.x)
-                .map(|_| {
-                    (0..size.y)
-                        .map(|_| Tile {
-                            tile_type: TileType::Empty,
-                        })
-                        .collect()
-                })
+                .map(|_| (0..size.y).map(|_| Tile::EMPTY).collect())
                 .collect(),
             updates: HashSet::new(),
-            tile_images: TileType::load_images().await,
+            shape_changed: true,
+            check_connectivity: false,
+            max_hull: 0,
+            tuned_mass: None,
+            tile_images,
+            empty_image: Image::gen_image_color(
+                Tile::SIZE_PIXELS as u16,
+                Tile::SIZE_PIXELS as u16,
+                BLANK,
+            ),
         }
     }
 
@@ -33,18 +65,20 @@ impl TileMap {
         for &update_index in &self.updates {
             let update_translation = update_index * Tile::SIZE_PIXELS;
 
-            match &self.tile_images[self[update_index].tile_type as usize] {
-                Some(image) => {
-                    texture.update_part(
-                        image,
-                        update_translation.x as i32,
-                        update_translation.y as i32,
-                        Tile::SIZE_PIXELS as i32,
-                        Tile::SIZE_PIXELS as i32,
-                    );
-                }
-                None => (),
-            }
+            let tile = self[update_index];
+
+            let image = match &self.tile_images[tile.tile_type as usize] {
+                Some(images) => &images[tile.damage_stage() as usize],
+                None => &self.empty_image,
+            };
+
+            texture.update_part(
+                image,
+                update_translation.x as i32,
+                update_translation.y as i32,
+                Tile::SIZE_PIXELS as i32,
+                Tile::SIZE_PIXELS as i32,
+            );
         }
 
         self.updates.clear();
@@ -53,23 +87,243 @@ impl TileMap {
     pub fn get(&self, index: UVec2) -> Option<&Tile> {
         let index = (index.x as usize, index.y as usize);
 
-        Some(self.contents.get(index.0)?.get(index.1)?)
+        self.contents.get(index.0)?.get(index.1)
     }
 
     pub fn get_mut(&mut self, index: UVec2) -> Option<&mut Tile> {
         let index = (index.x as usize, index.y as usize);
 
-        Some(self.contents.get_mut(index.0)?.get_mut(index.1)?)
+        self.contents.get_mut(index.0)?.get_mut(index.1)
     }
 
     pub fn set(&mut self, index: UVec2, tile: Tile) -> Option<()> {
-        *self.get_mut(index)? = tile;
+        let old = std::mem::replace(self.get_mut(index)?, tile);
+
+        if old.tile_type != TileType::Empty && tile.tile_type == TileType::Empty {
+            self.check_connectivity = true;
+        }
 
         self.updates.insert(index);
+        self.shape_changed = true;
 
         Some(())
     }
 
+    /// Damages a tile, replacing it with an empty tile if its health
+    /// runs out. Returns whether the tile was destroyed.
+    pub fn damage(&mut self, index: UVec2, amount: u32) -> bool {
+        let Some(&tile) = self.get(index) else {
+            return false;
+        };
+
+        if tile.tile_type.max_health() == 0 {
+            return false;
+        }
+
+        let health = tile.health.saturating_sub(amount.min(u8::MAX as u32) as u8);
+
+        if health == 0 {
+            self.set(index, Tile::EMPTY);
+            return true;
+        }
+
+        let damaged = Tile { health, ..tile };
+        self[index] = damaged;
+
+        if damaged.damage_stage() != tile.damage_stage() {
+            self.updates.insert(index);
+        }
+
+        false
+    }
+
+    /// The indicies of the tiles which have collision, in the order
+    /// their shapes appear in the compound made by `make_shape`.
+    pub fn solid_tiles(&self) -> Vec<UVec2> {
+        let size = self.size();
+
+        (0..size.x)
+            .flat_map(|x| (0..size.y).map(move |y| uvec2(x, y)))
+            .filter(|&index| self[index].tile_type.shape().is_some())
+            .collect()
+    }
+
+    /// The fraction of health the tiles have left out of what the map
+    /// was built with, from 0 to 1. Destroyed tiles count as having none.
+    pub fn hull(&self) -> f32 {
+        if self.max_hull == 0 {
+            return 0.0;
+        }
+
+        let health: u32 = self
+            .contents
+            .iter()
+            .flatten()
+            .map(|tile| tile.health as u32)
+            .sum();
+
+        (health as f32 / self.max_hull as f32).min(1.0)
+    }
+
+    /// Makes the map's current tiles what its hull is measured against,
+    /// once it has been built, loaded or edited.
+    pub fn reset_hull(&mut self) {
+        self.max_hull = self
+            .contents
+            .iter()
+            .flatten()
+            .map(|tile| tile.tile_type.max_health() as u32)
+            .sum();
+    }
+
+    /// Makes a compound of the shapes of each tile, with tiles of size
+    /// `tile_size` starting at `origin`. Returns `None` if there are no
+    /// tiles with collision.
+    pub fn make_shape(&self, tile_size: Vec2, origin: Vec2) -> Option<SharedShape> {
+        let shapes: Vec<_> = self
+            .tile_shapes(tile_size, origin)
+            .into_iter()
+            .map(|(_, position, shape)| (position, shape))
+            .collect();
+
+        if shapes.is_empty() {
+            return None;
+        }
+
+        Some(SharedShape::compound(shapes))
+    }
+
+    /// The material of each tile's shape, in the same order as the
+    /// shapes in `make_shape`.
+    pub fn materials(&self) -> Vec<PhysicsMaterial> {
+        self.solid_tiles()
+            .into_iter()
+            .map(|index| self[index].tile_type.material().unwrap())
+            .collect()
+    }
+
+    /// The combined mass properties of the tiles' shapes, using the
+    /// density of each tile's type.
+    pub fn mass_properties(&self, tile_size: Vec2, origin: Vec2) -> MassProperties {
+        self.tile_shapes(tile_size, origin)
+            .into_iter()
+            .map(|(index, position, shape)| {
+                shape
+                    .mass_properties(self[index].tile_type.density())
+                    .transform_by(&position)
+            })
+            .sum()
+    }
+
+    fn tile_shapes(
+        &self,
+        tile_size: Vec2,
+        origin: Vec2,
+    ) -> Vec<(UVec2, Isometry<Real>, SharedShape)> {
+        self.solid_tiles()
+            .into_iter()
+            .map(|index| {
+                let tile = self[index];
+                let translation = origin + index.as_vec2() * tile_size;
+
+                (
+                    index,
+                    Isometry::translation(translation.x, translation.y),
+                    tile.tile_type
+                        .shape()
+                        .unwrap()
+                        .get_collider_shape(tile_size),
+                )
+            })
+            .collect()
+    }
+
+    /// Groups the non-empty tiles into sets which are connected by their
+    /// edges, sorted from largest to smallest.
+    pub fn islands(&self) -> Vec<Vec<UVec2>> {
+        let size = self.size();
+        let mut visited = vec![vec![false; size.y as usize]; size.x as usize];
+        let mut islands = Vec::new();
+
+        for x in 0..size.x {
+            for y in 0..size.y {
+                if visited[x as usize][y as usize] || self[uvec2(x, y)].tile_type == TileType::Empty
+                {
+                    continue;
+                }
+
+                let mut island = Vec::new();
+                let mut stack = vec![uvec2(x, y)];
+                visited[x as usize][y as usize] = true;
+
+                while let Some(index) = stack.pop() {
+                    island.push(index);
+
+                    for offset in [ivec2(1, 0), ivec2(-1, 0), ivec2(0, 1), ivec2(0, -1)] {
+                        let neighbor = index.as_ivec2() + offset;
+
+                        if neighbor.cmplt(IVec2::ZERO).any() {
+                            continue;
+                        }
+
+                        let neighbor = neighbor.as_uvec2();
+
+                        match self.get(neighbor) {
+                            Some(tile) if tile.tile_type != TileType::Empty => (),
+                            _ => continue,
+                        }
+
+                        let visited = &mut visited[neighbor.x as usize][neighbor.y as usize];
+
+                        if !*visited {
+                            *visited = true;
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+
+                islands.push(island);
+            }
+        }
+
+        islands.sort_by_key(|island| std::cmp::Reverse(island.len()));
+
+        islands
+    }
+
+    /// Moves the given tiles into a new tile map of the same size,
+    /// leaving empty tiles in their place.
+    pub fn split_off(&mut self, tiles: &[UVec2]) -> TileMap {
+        let mut fragment = self.clone();
+
+        for column in &mut fragment.contents {
+            column.fill(Tile::EMPTY);
+        }
+
+        for &index in tiles {
+            fragment.contents[index.x as usize][index.y as usize] = self[index];
+            self.set(index, Tile::EMPTY);
+        }
+
+        fragment.updates.clear();
+        fragment.update_all();
+        fragment.shape_changed = true;
+        fragment.check_connectivity = false;
+        fragment.tuned_mass = None;
+        fragment.reset_hull();
+
+        fragment
+    }
+
+    pub fn tiles_of_type(&self, tile_type: TileType) -> Vec<UVec2> {
+        let size = self.size();
+
+        (0..size.x)
+            .flat_map(|x| (0..size.y).map(move |y| uvec2(x, y)))
+            .filter(|&index| self[index].tile_type == tile_type)
+            .collect()
+    }
+
     pub fn update_tile(&mut self, index: UVec2) -> Option<()> {
         if index.x >= self.size().x && index.y >= self.size().y {
             return None;
@@ -80,6 +334,18 @@ impl TileMap {
         Some(())
     }
 
+    /// Marks every tile to be redrawn, such as after the tile map is
+    /// given a new texture.
+    pub fn update_all(&mut self) {
+        let size = self.size();
+
+        for x in 0..size.x {
+            for y in 0..size.y {
+                self.updates.insert(uvec2(x, y));
+            }
+        }
+    }
+
     pub fn size(&self) -> UVec2 {
         uvec2(self.contents.len() as u32, self.contents[0].len() as u32)
     }
@@ -99,30 +365,88 @@ impl IndexMut<UVec2> for TileMap {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct Tile {
     pub tile_type: TileType,
+    /// The remaining health of the tile, out of the tile type's
+    /// `max_health`.
+    pub health: u8,
 }
 
 impl Tile {
     pub const SIZE_TEXELS: u32 = 8;
     pub const SIZE_PIXELS: u32 = Tile::SIZE_TEXELS * 2;
+
+    /// The number of images each tile type has, from undamaged to
+    /// nearly destroyed.
+    pub const DAMAGE_STAGES: u8 = 4;
+
+    pub const EMPTY: Tile = Tile {
+        tile_type: TileType::Empty,
+        health: 0,
+    };
+
+    pub fn new(tile_type: TileType) -> Self {
+        Self {
+            tile_type,
+            health: tile_type.max_health(),
+        }
+    }
+
+    pub fn damage_stage(self) -> u8 {
+        let max_health = self.tile_type.max_health() as u32;
+        let damage = max_health.saturating_sub(self.health as u32);
+
+        (damage * Self::DAMAGE_STAGES as u32 / (max_health + 1)) as u8
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
-#[repr(usize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[repr(u8)]
 pub enum TileType {
     Empty,
     Wall,
+    /// Pushes the object forwards from its position when it has a
+    /// `Motion` component.
+    Thruster,
+    /// Refuels ships with `Resources` which touch it.
+    Refuel,
+    /// Bounces back anything which hits it.
+    Bumper,
+    Ice,
 }
 
 impl TileType {
-    pub const TYPES: [TileType; 2] = [Self::Empty, Self::Wall];
+    pub const TYPES: [TileType; 6] = [
+        Self::Empty,
+        Self::Wall,
+        Self::Thruster,
+        Self::Refuel,
+        Self::Bumper,
+        Self::Ice,
+    ];
+
+    /// The next type in `TYPES` after this one, skipping `Empty`.
+    pub fn next_placeable(self) -> Self {
+        let mut index = self as usize;
+
+        loop {
+            index = (index + 1) % Self::TYPES.len();
+
+            if Self::TYPES[index] != Self::Empty {
+                return Self::TYPES[index];
+            }
+        }
+    }
 
     pub fn path_to_image(self) -> Option<&'static str> {
         match self {
             Self::Empty => None,
             Self::Wall => Some("assets/wall.png"),
+            Self::Thruster => Some("assets/thruster.png"),
+            Self::Refuel => Some("assets/refuel.png"),
+            Self::Bumper => Some("assets/bumper.png"),
+            Self::Ice => Some("assets/ice.png"),
         }
     }
 
@@ -130,15 +454,62 @@ impl TileType {
         match self {
             Self::Empty => None,
             Self::Wall => Some(TileShape::Box),
+            Self::Thruster => Some(TileShape::Box),
+            Self::Refuel => Some(TileShape::Box),
+            Self::Bumper => Some(TileShape::Box),
+            Self::Ice => Some(TileShape::Box),
+        }
+    }
+
+    pub fn material(self) -> Option<PhysicsMaterial> {
+        match self {
+            Self::Empty => None,
+            Self::Wall => Some(PhysicsMaterial::Metal),
+            Self::Thruster => Some(PhysicsMaterial::Metal),
+            Self::Refuel => Some(PhysicsMaterial::Metal),
+            Self::Bumper => Some(PhysicsMaterial::Rubber),
+            Self::Ice => Some(PhysicsMaterial::Ice),
         }
     }
 
-    pub async fn load_images() -> Vec<Option<Image>> {
+    /// A max health of 0 means the tile can't be damaged.
+    pub fn max_health(self) -> u8 {
+        match self {
+            Self::Empty => 0,
+            Self::Wall => 100,
+            Self::Thruster => 60,
+            Self::Refuel => 80,
+            Self::Bumper => 120,
+            Self::Ice => 40,
+        }
+    }
+
+    /// The mass per unit of area of the tile's shape.
+    pub fn density(self) -> f32 {
+        match self {
+            Self::Empty => 0.0,
+            Self::Wall => 1.0,
+            Self::Thruster => 0.5,
+            Self::Refuel => 1.0,
+            Self::Bumper => 1.2,
+            Self::Ice => 0.9,
+        }
+    }
+
+    pub async fn load_images() -> Vec<Option<Vec<Image>>> {
         let mut images = Vec::with_capacity(TileType::TYPES.len());
 
         for tile_type in TileType::TYPES {
             let image = match tile_type.path_to_image() {
-                Some(path) => Some(load_image(path).await.unwrap()),
+                Some(path) => {
+                    let image = load_image(path).await.unwrap();
+
+                    Some(
+                        (0..Tile::DAMAGE_STAGES)
+                            .map(|stage| crack_image(&image, stage))
+                            .collect(),
+                    )
+                }
                 None => None,
             };
 
@@ -149,6 +520,42 @@ impl TileType {
     }
 }
 
+/// Darkens random walks across a copy of the image, with more cracks
+/// for each damage stage. The same cracks are always made in the same
+/// order, so each stage adds to the cracks of the last.
+fn crack_image(image: &Image, stage: u8) -> Image {
+    let mut image = image.clone();
+    let size = uvec2(image.width as u32, image.height as u32);
+
+    let mut seed = 0x9e37_79b9_u32;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    for _ in 0..stage * 2 {
+        let mut position = uvec2(random() % size.x, random() % size.y);
+
+        for _ in 0..size.max_element() / 2 {
+            let color = image.get_pixel(position.x, position.y);
+            image.set_pixel(
+                position.x,
+                position.y,
+                Color::new(color.r * 0.3, color.g * 0.3, color.b * 0.3, color.a),
+            );
+
+            let step = ivec2((random() % 3) as i32 - 1, (random() % 3) as i32 - 1);
+            position = (position.as_ivec2() + step)
+                .clamp(IVec2::ZERO, size.as_ivec2() - 1)
+                .as_uvec2();
+        }
+    }
+
+    image
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum TileShape {
     Box,
@@ -168,6 +575,19 @@ impl TileShape {
             ),
         }
     }
+
+    /// The shape as a convex polygon scaled to the size of a tile, with
+    /// its top left corner at the origin.
+    pub fn get_collider_shape(self, tile_size: Vec2) -> SharedShape {
+        let points = self
+            .get_shape()
+            .vertices()
+            .iter()
+            .map(|point| point![point.x * tile_size.x, point.y * tile_size.y])
+            .collect();
+
+        SharedShape::convex_polyline(points).expect("Tile shapes should be convex")
+    }
 }
 
 fn loop_indicies(length: u32) -> Vec<[u32; 2]> {
@@ -0,0 +1,204 @@
+use crate::app::App;
+use crate::debug;
+use crate::force_field::predict_path;
+use crate::object::{Object, ObjectId};
+use crate::physics_world::PhysicsWorld;
+use macroquad::prelude::*;
+use rapier2d::prelude::*;
+
+/// Draws where the player's ship will drift if it stops thrusting,
+/// following force fields, up until the first collider it would hit.
+/// Other bodies are treated as if they stay where they are, and the ship
+/// as if it doesn't turn.
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    pub enabled: bool,
+    /// How far ahead the path is predicted.
+    pub seconds: f32,
+}
+
+/// Where a predicted path first hits a collider.
+#[derive(Clone, Copy, Debug)]
+pub struct Impact {
+    /// The position of the body's center of mass when it hits.
+    pub position: Vec2,
+    /// Seconds from now until it hits.
+    pub time: f32,
+    pub collider: ColliderHandle,
+}
+
+impl Trajectory {
+    const PATH_COLOR: Color = Color::new(0.5, 1.0, 0.7, 0.6);
+    const IMPACT_COLOR: Color = Color::new(1.0, 0.35, 0.25, 0.9);
+    const FONT_SIZE: f32 = 20.0;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            seconds: 4.0,
+        }
+    }
+
+    pub fn draw(
+        &self,
+        camera: &Camera2D,
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        player: Option<ObjectId>,
+    ) {
+        let Some(player) = objects.iter().find(|object| Some(object.id) == player) else {
+            return;
+        };
+
+        let thickness = debug::pixel_size(camera) * debug::LINE_PIXELS;
+
+        let (path, impact) = self.predict(physics_world, objects, player);
+        let ticks_per_second = App::FIXED_TICKS_PER_SEC as usize;
+
+        let start = Vec2::from(*physics_world.rigid_body_set[player.rigid_body].center_of_mass());
+        debug::draw_fading_path(start, &path, thickness, Self::PATH_COLOR, true);
+
+        // A dot for each second ahead
+        for point in path
+            .iter()
+            .skip(ticks_per_second - 1)
+            .step_by(ticks_per_second)
+        {
+            draw_circle(point.x, point.y, thickness * 2.0, Self::PATH_COLOR);
+        }
+
+        let Some(impact) = impact else {
+            return;
+        };
+
+        // The ship's outline where it will hit
+        let rigid_body = &physics_world.rigid_body_set[player.rigid_body];
+        let collider = &physics_world.collider_set[player.collider];
+        let shift = impact.position - Vec2::from(*rigid_body.center_of_mass());
+        let mut position = *collider.position();
+        position.translation.vector += Vector::from(shift);
+
+        debug::draw_shape(
+            collider.shape(),
+            &position,
+            thickness * 2.0,
+            Self::IMPACT_COLOR,
+        );
+
+        let target = &physics_world.collider_set[impact.collider];
+        debug::draw_shape(
+            target.shape(),
+            target.position(),
+            thickness,
+            Self::IMPACT_COLOR,
+        );
+
+        set_default_camera();
+
+        let label = format!("IMPACT {:.1}s", impact.time);
+        let screen = camera.world_to_screen(impact.position);
+        draw_text(
+            &label,
+            screen.x + 16.0,
+            screen.y - 16.0,
+            Self::FONT_SIZE,
+            Self::IMPACT_COLOR,
+        );
+
+        set_camera(camera);
+    }
+
+    /// The positions of `object`'s center of mass after each fixed tick
+    /// if it coasts, stopping where it would first hit another collider.
+    pub fn predict(
+        &self,
+        physics_world: &PhysicsWorld,
+        objects: &[Object],
+        object: &Object,
+    ) -> (Vec<Vec2>, Option<Impact>) {
+        let rigid_body = &physics_world.rigid_body_set[object.rigid_body];
+        let collider = &physics_world.collider_set[object.collider];
+        let start = Vec2::from(*rigid_body.center_of_mass());
+
+        let mut path = predict_path(
+            physics_world,
+            objects,
+            Some(object.id),
+            start,
+            (*rigid_body.linvel()).into(),
+            (self.seconds * App::FIXED_TICKS_PER_SEC) as u32,
+            App::FIXED_DELTA_TIME,
+        );
+
+        let Some(query_pipeline) = &physics_world.query_pipeline else {
+            return (path, None);
+        };
+
+        // Projectiles are too small and short lived to be worth avoiding,
+        // and bodies joined to the object move along with it
+        let projectiles: Vec<ColliderHandle> = objects
+            .iter()
+            .filter(|object| object.is_projectile())
+            .map(|object| object.collider)
+            .collect();
+        let joined: Vec<RigidBodyHandle> = physics_world
+            .impulse_joint_set
+            .attached_joints(object.rigid_body)
+            .flat_map(|(body1, body2, _, _)| [body1, body2])
+            .collect();
+        let predicate = |handle, collider: &Collider| {
+            !projectiles.contains(&handle)
+                && collider
+                    .parent()
+                    .is_none_or(|parent| !joined.contains(&parent))
+        };
+
+        let filter = QueryFilter::default()
+            .groups(collider.collision_groups())
+            .exclude_rigid_body(object.rigid_body)
+            .exclude_sensors()
+            .predicate(&predicate);
+
+        let mut previous = start;
+
+        for (tick, &point) in path.iter().enumerate() {
+            let mut position = *collider.position();
+            position.translation.vector += Vector::from(previous - start);
+
+            let hit = query_pipeline.cast_shape(
+                &physics_world.rigid_body_set,
+                &physics_world.collider_set,
+                &position,
+                &(point - previous).into(),
+                collider.shape(),
+                1.0,
+                false,
+                filter,
+            );
+
+            if let Some((hit, toi)) = hit {
+                let position = previous.lerp(point, toi.toi);
+                path.truncate(tick);
+                path.push(position);
+
+                let impact = Impact {
+                    position,
+                    time: (tick as f32 + toi.toi) * App::FIXED_DELTA_TIME,
+                    collider: hit,
+                };
+
+                return (path, Some(impact));
+            }
+
+            previous = point;
+        }
+
+        (path, None)
+    }
+}
+
+impl Default for Trajectory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
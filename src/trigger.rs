@@ -0,0 +1,255 @@
+use crate::collision_layer::CollisionLayer;
+use crate::object::ObjectId;
+use rapier2d::prelude::*;
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TriggerKind {
+    Enter,
+    Exit,
+}
+
+/// A collider starting or stopping touching a sensor during a physics
+/// step.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SensorEvent {
+    pub sensor: ColliderHandle,
+    pub other: ColliderHandle,
+    pub kind: TriggerKind,
+}
+
+/// An object entering or leaving the sensor of a trigger zone's object.
+/// It's sent to the components of both objects.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TriggerEvent {
+    pub zone: ObjectId,
+    pub visitor: ObjectId,
+    pub kind: TriggerKind,
+}
+
+/// What a `TriggerZone` component does when an object enters it.
+#[derive(Clone, Debug)]
+pub enum TriggerAction {
+    /// Becomes where the player respawns.
+    Checkpoint,
+    /// Ends the level, locking the player's controls until they respawn.
+    LevelExit,
+    /// Shows a message on the HUD, for scripted events.
+    Message(String),
+    /// Opens a door, like a hangar door, by disabling the collider of its
+    /// object while anything is in the zone.
+    Door(ObjectId),
+}
+
+impl TriggerAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Checkpoint => "CHECKPOINT",
+            Self::LevelExit => "LEVEL COMPLETE",
+            Self::Message(_) => "MESSAGE",
+            Self::Door(_) => "DOOR",
+        }
+    }
+}
+
+/// A sensor which sends enter and exit events, for trigger zones.
+pub fn trigger_collider(shape: SharedShape) -> ColliderBuilder {
+    ColliderBuilder::new(shape)
+        .sensor(true)
+        .active_events(ActiveEvents::COLLISION_EVENTS)
+        .collision_groups(CollisionLayer::Sensor.groups())
+}
+
+/// Collects the events of sensors during a physics step.
+#[derive(Default)]
+pub struct SensorEventCollector {
+    events: Mutex<Vec<SensorEvent>>,
+}
+
+impl SensorEventCollector {
+    pub fn into_events(self) -> Vec<SensorEvent> {
+        self.events.into_inner().unwrap_or_default()
+    }
+}
+
+impl EventHandler for SensorEventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        event: CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        if !event.sensor() {
+            return;
+        }
+
+        let (collider1, collider2) = (event.collider1(), event.collider2());
+
+        // Removed colliders are no longer in the set, so whichever one is
+        // left says which was the sensor
+        let first_is_sensor = match (colliders.get(collider1), colliders.get(collider2)) {
+            (Some(first), _) if first.is_sensor() => true,
+            (_, Some(second)) if second.is_sensor() => false,
+            (None, _) => true,
+            (_, None) => false,
+            _ => return,
+        };
+
+        let (sensor, other) = if first_is_sensor {
+            (collider1, collider2)
+        } else {
+            (collider2, collider1)
+        };
+
+        let kind = if event.started() {
+            TriggerKind::Enter
+        } else {
+            TriggerKind::Exit
+        };
+
+        if let Ok(mut events) = self.events.lock() {
+            events.push(SensorEvent {
+                sensor,
+                other,
+                kind,
+            });
+        }
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_world::PhysicsWorld;
+
+    #[test]
+    fn moving_through_sensor_enters_and_exits_once() {
+        let mut physics_world = PhysicsWorld::new();
+
+        let (_, sensor) = physics_world.add_rigidbody(
+            RigidBodyBuilder::fixed().build(),
+            trigger_collider(SharedShape::ball(8.0)).build(),
+        );
+
+        let (_, ship) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![-20.0, 0.0])
+                .linvel(vector![30.0, 0.0])
+                .build(),
+            ColliderBuilder::ball(2.0)
+                .collision_groups(CollisionLayer::Ship.groups())
+                .build(),
+        );
+
+        let mut events = Vec::new();
+
+        // Two seconds takes the ship from one side of the sensor to the
+        // other
+        for _ in 0..120 {
+            physics_world.step();
+            events.extend(physics_world.sensor_events.iter().copied());
+        }
+
+        assert_eq!(
+            events,
+            [
+                SensorEvent {
+                    sensor,
+                    other: ship,
+                    kind: TriggerKind::Enter,
+                },
+                SensorEvent {
+                    sensor,
+                    other: ship,
+                    kind: TriggerKind::Exit,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn removing_body_inside_sensor_exits() {
+        let mut physics_world = PhysicsWorld::new();
+
+        let (_, sensor) = physics_world.add_rigidbody(
+            RigidBodyBuilder::fixed().build(),
+            trigger_collider(SharedShape::ball(8.0)).build(),
+        );
+
+        let (rigid_body, ship) = physics_world.add_rigidbody(
+            RigidBodyBuilder::dynamic().build(),
+            ColliderBuilder::ball(2.0)
+                .collision_groups(CollisionLayer::Ship.groups())
+                .build(),
+        );
+
+        physics_world.step();
+        physics_world.remove_rigidbody(rigid_body);
+        physics_world.step();
+
+        assert_eq!(
+            physics_world.sensor_events,
+            [SensorEvent {
+                sensor,
+                other: ship,
+                kind: TriggerKind::Exit,
+            }]
+        );
+    }
+
+    #[test]
+    fn removing_sensor_around_body_exits() {
+        // Either collider of the event may be the removed one, depending on
+        // which was added first
+        for sensor_first in [true, false] {
+            let mut physics_world = PhysicsWorld::new();
+
+            let add_sensor = |physics_world: &mut PhysicsWorld| {
+                physics_world.add_rigidbody(
+                    RigidBodyBuilder::fixed().build(),
+                    trigger_collider(SharedShape::ball(8.0)).build(),
+                )
+            };
+            let add_ship = |physics_world: &mut PhysicsWorld| {
+                physics_world.add_rigidbody(
+                    RigidBodyBuilder::dynamic().build(),
+                    ColliderBuilder::ball(2.0)
+                        .collision_groups(CollisionLayer::Ship.groups())
+                        .build(),
+                )
+            };
+
+            let ((sensor_body, sensor), (_, ship)) = if sensor_first {
+                let sensor = add_sensor(&mut physics_world);
+                (sensor, add_ship(&mut physics_world))
+            } else {
+                let ship = add_ship(&mut physics_world);
+                (add_sensor(&mut physics_world), ship)
+            };
+
+            physics_world.step();
+            physics_world.remove_rigidbody(sensor_body);
+            physics_world.step();
+
+            assert_eq!(
+                physics_world.sensor_events,
+                [SensorEvent {
+                    sensor,
+                    other: ship,
+                    kind: TriggerKind::Exit,
+                }]
+            );
+        }
+    }
+}